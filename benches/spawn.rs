@@ -0,0 +1,43 @@
+//! How the size of a level affects spawning it, in time and in assets:
+//!
+//! `cargo bench --bench spawn`
+
+use criterion::{criterion_group, criterion_main, BatchSize, BenchmarkId, Criterion};
+
+use pist_off::bench;
+
+fn spawn_levels(c: &mut Criterion) {
+    let mut group = c.benchmark_group("spawn_level");
+
+    for block_count in 1..=10 {
+        // The counts don't change between runs, so report them once up front
+        let mut world = bench::world();
+        bench::spawn_level(&mut world, block_count);
+        let counts = bench::counts(&world);
+        println!(
+            "{block_count:>2} per axis: {:>5} entities, {} meshes, {} materials",
+            counts.entities, counts.meshes, counts.materials,
+        );
+
+        group.bench_with_input(
+            BenchmarkId::from_parameter(block_count),
+            &block_count,
+            |b, &block_count| {
+                b.iter_batched(
+                    bench::world,
+                    |mut world| {
+                        bench::spawn_level(&mut world, block_count);
+                        // Hand the world back so dropping it isn't timed
+                        world
+                    },
+                    BatchSize::LargeInput,
+                );
+            },
+        );
+    }
+
+    group.finish();
+}
+
+criterion_group!(benches, spawn_levels);
+criterion_main!(benches);
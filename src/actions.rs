@@ -1,29 +1,60 @@
+use bevy::input::mouse::{MouseMotion, MouseScrollUnit, MouseWheel};
 use bevy::prelude::*;
 
-use crate::actions::game_control::{get_movement, GameControl};
+use crate::actions::game_control::{get_camera_view, get_movement, GameControl};
 use crate::GameState;
 
 mod game_control;
+mod touch;
+
+pub use self::game_control::CameraView;
 
 pub struct ActionsPlugin;
 
-// This plugin listens for keyboard input and converts the input into Actions
-// Actions can then be used as a resource in other systems to act on the player input.
+// This plugin listens for keyboard, mouse, gamepad and touch input and converts the input
+// into Actions. Actions can then be used as a resource in other systems to act on the
+// player input.
 impl Plugin for ActionsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<Actions>().add_systems(
             Update,
-            set_movement_actions.run_if(in_state(GameState::Playing)),
+            (
+                set_movement_actions,
+                set_drag_actions,
+                set_zoom_actions,
+                // touches add onto whatever the mouse did, so they go last
+                touch::set_touch_actions,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
         );
     }
 }
 
 #[derive(Default, Resource)]
 pub struct Actions {
+    /// Held rotation direction from the keyboard or a gamepad stick.
     pub player_rotation: Option<Vec2>,
+    /// A viewpoint the camera should snap to.
+    pub camera_view: Option<CameraView>,
+    /// How far the cube was dragged around this frame, in logical pixels.
+    pub orbit_drag: Option<Vec2>,
+    /// Factor to scale the camera's distance from the cube by. Less than one
+    /// zooms in, greater than one zooms out.
+    pub zoom: Option<f32>,
+    /// Touches that were released this frame without dragging, which should be
+    /// treated as clicks.
+    pub touch_taps: Vec<u64>,
 }
 
-pub fn set_movement_actions(mut actions: ResMut<Actions>, keyboard_input: Res<Input<KeyCode>>) {
+pub fn set_movement_actions(
+    mut actions: ResMut<Actions>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_axes: Res<Axis<GamepadAxis>>,
+) {
+    actions.camera_view = get_camera_view(&keyboard_input);
+
     let player_rotation = Vec2::new(
         get_movement(GameControl::Right, &keyboard_input)
             - get_movement(GameControl::Left, &keyboard_input),
@@ -31,9 +62,56 @@ pub fn set_movement_actions(mut actions: ResMut<Actions>, keyboard_input: Res<In
             - get_movement(GameControl::Down, &keyboard_input),
     );
 
-    if player_rotation == Vec2::ZERO {
-        actions.player_rotation = None;
-    } else {
+    if player_rotation != Vec2::ZERO {
         actions.player_rotation = Some(player_rotation.normalize());
+        return;
     }
+
+    // Sticks are already analog and deadzoned by Bevy, so just use the first one
+    // that is being pushed.
+    actions.player_rotation = gamepads.iter().find_map(|gamepad| {
+        let stick = Vec2::new(
+            gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickX))?,
+            gamepad_axes.get(GamepadAxis::new(gamepad, GamepadAxisType::LeftStickY))?,
+        );
+
+        (stick != Vec2::ZERO).then(|| stick.clamp_length_max(1.0))
+    });
+}
+
+/// Dragging with the right mouse button orbits the cube, since the left button
+/// is used for pushing blocks.
+pub fn set_drag_actions(
+    mut actions: ResMut<Actions>,
+    mouse_input: Res<Input<MouseButton>>,
+    mut motion: EventReader<MouseMotion>,
+) {
+    let delta: Vec2 = motion.read().map(|motion| motion.delta).sum();
+
+    actions.orbit_drag = if mouse_input.pressed(MouseButton::Right) && delta != Vec2::ZERO {
+        // screen space y goes down, but dragging "up" should roll the cube up
+        Some(Vec2::new(delta.x, -delta.y))
+    } else {
+        None
+    };
+}
+
+/// How many logical pixels of scrolling count as one "line" on the mouse wheel.
+const PIXELS_PER_SCROLL_LINE: f32 = 50.0;
+
+pub fn set_zoom_actions(mut actions: ResMut<Actions>, mut wheel: EventReader<MouseWheel>) {
+    let lines: f32 = wheel
+        .read()
+        .map(|wheel| match wheel.unit {
+            MouseScrollUnit::Line => wheel.y,
+            MouseScrollUnit::Pixel => wheel.y / PIXELS_PER_SCROLL_LINE,
+        })
+        .sum();
+
+    actions.zoom = if lines == 0.0 {
+        None
+    } else {
+        // scrolling "up" zooms in
+        Some(0.9_f32.powf(lines))
+    };
 }
@@ -1,4 +1,4 @@
-use bevy::prelude::{Input, KeyCode, Res};
+use bevy::prelude::{Input, KeyCode, Res, Vec3};
 
 pub enum GameControl {
     Up,
@@ -33,3 +33,51 @@ pub fn get_movement(control: GameControl, input: &Res<Input<KeyCode>>) -> f32 {
         0.0
     }
 }
+
+/// A fixed viewpoint the camera can be snapped to.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CameraView {
+    /// Look straight at the face of the cube pointing in this direction.
+    Face(Vec3),
+    /// Look at the cube isometrically, from the corner in this direction.
+    Corner(Vec3),
+    /// Go back to the camera pose from the start of the game.
+    Reset,
+}
+
+const FACE_KEYS: [(KeyCode, Vec3); 6] = [
+    (KeyCode::Key1, Vec3::Z),
+    (KeyCode::Key2, Vec3::X),
+    (KeyCode::Key3, Vec3::NEG_Z),
+    (KeyCode::Key4, Vec3::NEG_X),
+    (KeyCode::Key5, Vec3::Y),
+    (KeyCode::Key6, Vec3::NEG_Y),
+];
+
+// Holding shift looks from the matching bottom corner instead
+const CORNER_KEYS: [(KeyCode, Vec3); 4] = [
+    (KeyCode::Key7, Vec3::new(-1.0, 1.0, 1.0)),
+    (KeyCode::Key8, Vec3::new(1.0, 1.0, 1.0)),
+    (KeyCode::Key9, Vec3::new(1.0, 1.0, -1.0)),
+    (KeyCode::Key0, Vec3::new(-1.0, 1.0, -1.0)),
+];
+
+pub fn get_camera_view(input: &Res<Input<KeyCode>>) -> Option<CameraView> {
+    if input.just_pressed(KeyCode::R) {
+        return Some(CameraView::Reset);
+    }
+
+    if let Some(&(_, direction)) = FACE_KEYS.iter().find(|(key, _)| input.just_pressed(*key)) {
+        return Some(CameraView::Face(direction));
+    }
+
+    let (_, direction) = CORNER_KEYS
+        .iter()
+        .find(|(key, _)| input.just_pressed(*key))?;
+
+    if input.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        Some(CameraView::Corner(*direction * Vec3::new(1.0, -1.0, 1.0)))
+    } else {
+        Some(CameraView::Corner(*direction))
+    }
+}
@@ -0,0 +1,170 @@
+//! Touch screen gestures, so the game can be played on phones and tablets.
+//!
+//! One finger drags the cube around, two fingers pinch to zoom, and a tap that
+//! never turned into a drag is left for `bevy_mod_picking` to push a block with.
+
+use bevy::prelude::*;
+use bevy::utils::{HashMap, HashSet};
+
+use super::Actions;
+
+/// How far a touch can wander, in logical pixels, and still count as a tap.
+const TAP_MAX_DISTANCE: f32 = 12.0;
+
+#[derive(Default)]
+pub struct TouchTracker {
+    /// Where each active touch was on the previous frame. [`Touch::delta`] only
+    /// updates when a touch moves, so it can't tell us about this frame alone.
+    last_positions: HashMap<u64, Vec2>,
+    /// Touches that moved too far or joined a pinch, so they can't be taps.
+    dragged: HashSet<u64>,
+}
+
+pub fn set_touch_actions(
+    mut actions: ResMut<Actions>,
+    touches: Res<Touches>,
+    mut tracker: Local<TouchTracker>,
+) {
+    actions.touch_taps.clear();
+
+    for touch in touches.iter_just_released() {
+        let id = touch.id();
+        tracker.last_positions.remove(&id);
+
+        if !tracker.dragged.remove(&id) && touch.distance().length() <= TAP_MAX_DISTANCE {
+            actions.touch_taps.push(id);
+        }
+    }
+
+    for touch in touches.iter_just_canceled() {
+        tracker.last_positions.remove(&touch.id());
+        tracker.dragged.remove(&touch.id());
+    }
+
+    let pinching = touches.iter().count() > 1;
+
+    let mut moves = Vec::new();
+    for touch in touches.iter() {
+        let id = touch.id();
+        let position = touch.position();
+        let previous = tracker
+            .last_positions
+            .insert(id, position)
+            .unwrap_or(position);
+
+        if pinching || touch.distance().length() > TAP_MAX_DISTANCE {
+            tracker.dragged.insert(id);
+        }
+
+        moves.push((id, previous, position));
+    }
+
+    match moves[..] {
+        [(id, previous, position)] if tracker.dragged.contains(&id) => {
+            let delta = position - previous;
+            if delta != Vec2::ZERO {
+                // screen space y goes down, just like for mouse drags
+                let drag = Vec2::new(delta.x, -delta.y);
+                actions.orbit_drag = Some(actions.orbit_drag.unwrap_or_default() + drag);
+            }
+        }
+        [(_, previous_a, position_a), (_, previous_b, position_b)] => {
+            let previous_span = previous_a.distance(previous_b);
+            let span = position_a.distance(position_b);
+
+            if previous_span > 0.0 && span > 0.0 && span != previous_span {
+                // spreading fingers apart should bring the camera closer
+                let zoom = previous_span / span;
+                actions.zoom = Some(actions.zoom.unwrap_or(1.0) * zoom);
+            }
+        }
+        _ => {}
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::input::touch::TouchPhase;
+    use bevy::input::InputPlugin;
+
+    use super::*;
+
+    fn touch_app() -> App {
+        let mut app = App::new();
+        app.add_plugins(InputPlugin)
+            .init_resource::<Actions>()
+            .add_systems(Update, set_touch_actions);
+        app
+    }
+
+    fn touch(app: &mut App, id: u64, phase: TouchPhase, x: f32, y: f32) {
+        app.world.send_event(TouchInput {
+            phase,
+            position: Vec2::new(x, y),
+            force: None,
+            id,
+        });
+    }
+
+    #[test]
+    fn one_finger_drag_orbits() {
+        let mut app = touch_app();
+
+        touch(&mut app, 0, TouchPhase::Started, 100.0, 100.0);
+        app.update();
+        assert_eq!(app.world.resource::<Actions>().orbit_drag, None);
+
+        touch(&mut app, 0, TouchPhase::Moved, 140.0, 80.0);
+        app.update();
+        assert_eq!(
+            app.world.resource::<Actions>().orbit_drag,
+            Some(Vec2::new(40.0, 20.0)),
+        );
+
+        touch(&mut app, 0, TouchPhase::Ended, 140.0, 80.0);
+        app.update();
+        assert!(app.world.resource::<Actions>().touch_taps.is_empty());
+    }
+
+    #[test]
+    fn two_finger_pinch_zooms() {
+        let mut app = touch_app();
+
+        touch(&mut app, 0, TouchPhase::Started, 100.0, 100.0);
+        touch(&mut app, 1, TouchPhase::Started, 200.0, 100.0);
+        app.update();
+
+        touch(&mut app, 1, TouchPhase::Moved, 300.0, 100.0);
+        app.update();
+
+        let actions = app.world.resource::<Actions>();
+        assert_eq!(actions.zoom, Some(0.5));
+        assert_eq!(actions.orbit_drag, None);
+
+        touch(&mut app, 0, TouchPhase::Ended, 100.0, 100.0);
+        touch(&mut app, 1, TouchPhase::Ended, 300.0, 100.0);
+        app.update();
+        assert!(app.world.resource::<Actions>().touch_taps.is_empty());
+    }
+
+    #[test]
+    fn tap_without_drag() {
+        let mut app = touch_app();
+
+        touch(&mut app, 3, TouchPhase::Started, 100.0, 100.0);
+        app.update();
+
+        // a little wobble is still a tap
+        touch(&mut app, 3, TouchPhase::Moved, 104.0, 97.0);
+        app.update();
+        assert_eq!(app.world.resource::<Actions>().orbit_drag, None);
+
+        touch(&mut app, 3, TouchPhase::Ended, 104.0, 97.0);
+        app.update();
+        assert_eq!(app.world.resource::<Actions>().touch_taps, vec![3]);
+
+        // and it only gets reported once
+        app.update();
+        assert!(app.world.resource::<Actions>().touch_taps.is_empty());
+    }
+}
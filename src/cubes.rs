@@ -1,37 +1,66 @@
 mod activation;
+mod code;
+mod connections;
 mod graph;
 mod highlight;
+mod layout;
+mod materials;
+mod palette;
+mod tween;
 
 use bevy::gltf::Gltf;
 use bevy::log;
 use bevy::prelude::*;
 use bevy_mod_picking::highlight::DefaultHighlightingPlugin;
 use bevy_mod_picking::DefaultPickingPlugins;
-use rand::seq::SliceRandom;
 
 use crate::cubes::highlight::{HighlightableBundle, UnpickableBundle};
 use crate::loading::GLTFAssets;
+use crate::settings::Settings;
+use crate::theme::CurrentTheme;
 use crate::GameState;
 
 use self::activation::ActivatePlugin;
+use self::connections::ConnectionsPlugin;
 use self::graph::GraphPlugin;
 use self::highlight::HighlightPlugin;
-
-pub use self::activation::{ToggleEvent, ToggleTimer};
-pub use self::graph::EntityGraph;
+use self::layout::block_positions;
+use self::materials::BlockAssets;
+use self::palette::{BlockColor, PalettePlugin};
+use self::tween::TweenPlugin;
+
+pub use self::activation::PushEvent;
+pub use self::code::{PuzzleCode, PuzzleCodeError, GENERATOR_VERSION, MAX_BLOCK_COUNT};
+pub use self::graph::{CascadeEvent, EntityGraph, KnockOutEvent, PendingCascade, PropagateMode};
+pub use self::highlight::Spotlight;
+pub use self::layout::PuzzleLayout;
+pub use self::materials::BlockMaterial;
+pub use self::palette::{ColorPalette, StateColors};
+pub use self::tween::{BlockTween, PushAnimation, ToggleEvent};
 
 pub struct CubePlugin;
 
 impl Plugin for CubePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<BlockCount>()
+            .init_resource::<BlockAssets>()
+            .add_event::<NewLevel>()
+            .add_event::<RestartLevel>()
+            .add_event::<LoadPuzzle>()
             .add_plugins(
                 DefaultPickingPlugins
                     .build()
                     // disable the default material based highlighting
                     .disable::<DefaultHighlightingPlugin>(),
             )
-            .add_plugins((ActivatePlugin, GraphPlugin, HighlightPlugin))
+            .add_plugins((
+                ActivatePlugin,
+                ConnectionsPlugin,
+                GraphPlugin,
+                HighlightPlugin,
+                PalettePlugin,
+                TweenPlugin,
+            ))
             .add_systems(
                 Update,
                 spawn_cuby
@@ -39,12 +68,20 @@ impl Plugin for CubePlugin {
                     // This seems more complicated but I think it's the simplest way to
                     // run "in multiple schedules". It seems to work how I want anyway...
                     .run_if(
-                        state_changed::<GameState>().and_then(
-                            in_state(GameState::Menu).or_else(in_state(GameState::Reset)),
-                        ),
+                        state_changed::<GameState>()
+                            .and_then(in_state(GameState::Menu))
+                            .or_else(on_event::<NewLevel>())
+                            .or_else(on_event::<RestartLevel>())
+                            .or_else(on_event::<LoadPuzzle>()),
                     ),
             )
-            .add_systems(OnEnter(GameState::Playing), show_cuby.after(spawn_cuby));
+            .add_systems(OnEnter(GameState::Playing), show_cuby.after(spawn_cuby))
+            .add_systems(
+                Update,
+                materials::update_block_materials
+                    .before(spawn_cuby)
+                    .run_if(resource_changed::<CurrentTheme>()),
+            );
     }
 }
 
@@ -52,6 +89,23 @@ impl Plugin for CubePlugin {
 #[derive(Component)]
 pub struct CubeFrame;
 
+/// Everything that makes up a single level, which gets replaced when a new one is spawned.
+pub type IsGameEntity = Or<(With<CubeFrame>, With<EntityGraph>)>;
+
+/// Throw away the current level and build a new one, sized by [`BlockCount`].
+#[derive(Event)]
+pub struct NewLevel;
+
+/// Throw away the current level and build the exact same puzzle again, from the
+/// beginning.
+#[derive(Event)]
+pub struct RestartLevel;
+
+/// Throw away the current level and build the puzzle described by a code. The
+/// [`BlockCount`] should be set to match.
+#[derive(Event)]
+pub struct LoadPuzzle(pub PuzzleCode);
+
 /// The number of blocks per axis to spawn into the cube.
 // TODO: allow this to be a number of blocks per *side* instead, so we could start
 // at 1 and work our way up.
@@ -64,6 +118,24 @@ impl Default for BlockCount {
     }
 }
 
+/// How much of a block's width to leave between the outermost blocks and the frame.
+const FRAME_MARGIN_BLOCKS: f32 = 0.25;
+
+impl BlockCount {
+    /// How much to scale the blocks down by to fit inside the frame. The gap
+    /// to the frame stays the same relative to a block, so bigger cubes don't
+    /// waste more and more of the frame on empty space.
+    pub fn inner_scale(&self) -> f32 {
+        let per_axis = f32::from(self.0);
+        per_axis / (per_axis + FRAME_MARGIN_BLOCKS)
+    }
+
+    /// The width of a single block, relative to the frame's size.
+    pub fn block_size(&self) -> f32 {
+        self.inner_scale() / f32::from(self.0)
+    }
+}
+
 /// The interactable components of the cube.
 #[derive(Component, Default, Debug)]
 pub struct Block {
@@ -76,7 +148,7 @@ pub struct Block {
 #[derive(Bundle, Default)]
 struct BlockBundle {
     pub block: Block,
-    pub toggle_timer: ToggleTimer,
+    pub tween: BlockTween,
     pub highlight: HighlightableBundle,
 }
 
@@ -97,59 +169,67 @@ impl BlockState {
     }
 }
 
-const ALL_COLORS: &[Color] = &[
-    Color::ALICE_BLUE,
-    Color::AQUAMARINE,
-    Color::AZURE,
-    Color::BLUE,
-    Color::CRIMSON,
-    Color::CYAN,
-    Color::DARK_GREEN,
-    Color::FUCHSIA,
-    Color::GOLD,
-    Color::GREEN,
-    Color::INDIGO,
-    Color::LIME_GREEN,
-    Color::MAROON,
-    Color::MIDNIGHT_BLUE,
-    Color::NAVY,
-    Color::OLIVE,
-    Color::ORANGE,
-    Color::ORANGE_RED,
-    Color::PINK,
-    Color::PURPLE,
-    Color::RED,
-    Color::SEA_GREEN,
-    Color::TEAL,
-    Color::TOMATO,
-    Color::TURQUOISE,
-    Color::VIOLET,
-    Color::YELLOW,
-    Color::YELLOW_GREEN,
-];
-
 fn spawn_cuby(
     mut commands: Commands,
+    game_entities: Query<Entity, IsGameEntity>,
     gltf_assets: Res<Assets<Gltf>>,
     gltf: Res<GLTFAssets>,
-    mut meshes: ResMut<Assets<Mesh>>,
+    mut block_assets: ResMut<BlockAssets>,
     mut materials: ResMut<Assets<StandardMaterial>>,
     block_count: Res<BlockCount>,
-) -> Vec<Entity> {
-    let root = gltf_assets.get(&gltf.cuby).unwrap();
+    state: Res<State<GameState>>,
+    mut restart: EventReader<RestartLevel>,
+    mut load: EventReader<LoadPuzzle>,
+    layout: Option<Res<PuzzleLayout>>,
+    settings: Res<Settings>,
+    theme: Res<CurrentTheme>,
+    animation: Res<PushAnimation>,
+) -> (Vec<Entity>, PuzzleLayout) {
+    for entity in &game_entities {
+        commands.entity(entity).despawn_recursive();
+    }
 
-    let cube = meshes.add(shape::Cube::default().into());
+    let restarting = restart.read().count() > 0;
+    let code = load.read().last().map(|load| load.0);
+
+    // Rebuild the same puzzle when restarting, as long as there's one to rebuild
+    let layout = match (code, layout) {
+        (Some(code), _) => code.layout(),
+        (None, Some(layout)) if restarting && layout.block_count == block_count.0 => layout.clone(),
+        _ => PuzzleLayout::random(block_count.0),
+    };
+
+    // The theme's frame could still be loading if it was only just picked, or
+    // be missing its scene if the theme file was edited by hand
+    let frame_scene = |handle: &Handle<Gltf>| {
+        gltf_assets
+            .get(handle)
+            .and_then(|frame| frame.named_scenes.get("Scene"))
+    };
+    let scene = frame_scene(&theme.frame)
+        .or_else(|| frame_scene(&gltf.cuby))
+        .unwrap()
+        .clone();
+
+    block_assets.clear_materials();
 
     let mut blocks = Vec::new();
 
     // use a parent entity to make it simpler to scale down the inner cubes
     let middleman = commands
         .spawn(SpatialBundle {
-            transform: Transform::from_scale(Vec3::splat(0.8)),
+            transform: Transform::from_scale(Vec3::splat(block_count.inner_scale())),
             ..default()
         })
         .with_children(|parent| {
-            blocks = spawn_blocks(parent, cube, &mut materials, block_count);
+            blocks = spawn_blocks(
+                parent,
+                &mut block_assets,
+                &mut materials,
+                &layout,
+                settings.accessibility.palette,
+                &animation,
+            );
         })
         .id();
 
@@ -157,15 +237,20 @@ fn spawn_cuby(
         .spawn((
             CubeFrame,
             SceneBundle {
-                scene: root.named_scenes["Scene"].clone(),
-                visibility: Visibility::Hidden,
+                scene,
+                // Restarting happens mid-game, otherwise wait for the game to start
+                visibility: if *state.get() == GameState::Playing {
+                    Visibility::Visible
+                } else {
+                    Visibility::Hidden
+                },
                 ..default()
             },
             UnpickableBundle::default(),
         ))
         .add_child(middleman);
 
-    blocks
+    (blocks, layout)
 }
 
 fn show_cuby(mut query: Query<&mut Visibility, With<CubeFrame>>) {
@@ -174,108 +259,142 @@ fn show_cuby(mut query: Query<&mut Visibility, With<CubeFrame>>) {
 
 fn spawn_blocks(
     parent: &mut ChildBuilder,
-    cube_mesh: Handle<Mesh>,
+    block_assets: &mut BlockAssets,
     materials: &mut Assets<StandardMaterial>,
-    block_count: Res<BlockCount>,
+    layout: &PuzzleLayout,
+    palette: ColorPalette,
+    animation: &PushAnimation,
 ) -> Vec<Entity> {
     let mut ids = Vec::new();
 
-    let num_cubes_per_axis = block_count.0;
+    let num_cubes_per_axis = layout.block_count;
 
     let cube_scale = 1.0 / f32::from(num_cubes_per_axis);
-    let mut color_idx: usize = rand::random();
-
-    for i in gen_combinations(num_cubes_per_axis) {
-        let i_f = f32::from(i);
-        for j in gen_combinations(num_cubes_per_axis) {
-            let j_f = f32::from(j);
-            for k in gen_combinations(num_cubes_per_axis) {
-                let k_f = f32::from(k);
-
-                let mut axes = Vec::new();
-                if i.abs() == num_cubes_per_axis {
-                    axes.push(Vec3::X * i_f.signum());
-                }
-                if j.abs() == num_cubes_per_axis {
-                    axes.push(Vec3::Y * j_f.signum());
-                }
-                if k.abs() == num_cubes_per_axis {
-                    axes.push(Vec3::Z * k_f.signum());
-                }
 
-                let x_pos = (i_f - 0.5 * i_f.signum()) * cube_scale;
-                let y_pos = (j_f - 0.5 * j_f.signum()) * cube_scale;
-                let z_pos = (k_f - 0.5 * k_f.signum()) * cube_scale;
+    let colors = palette.block_colors(layout);
+
+    for (index, ((i, j, k), block_layout)) in block_positions(num_cubes_per_axis)
+        .zip(&layout.blocks)
+        .enumerate()
+    {
+        let (i_f, j_f, k_f) = (f32::from(i), f32::from(j), f32::from(k));
+
+        let x_pos = (i_f - 0.5 * i_f.signum()) * cube_scale;
+        let y_pos = (j_f - 0.5 * j_f.signum()) * cube_scale;
+        let z_pos = (k_f - 0.5 * k_f.signum()) * cube_scale;
+
+        let translation = Vec3::new(x_pos, y_pos, z_pos);
 
-                let translation = Vec3::new(x_pos, y_pos, z_pos);
+        let out_direction = block_layout.out_direction.unwrap_or(Vec3::Z);
 
-                let color: Color = ALL_COLORS[color_idx % ALL_COLORS.len()];
-                color_idx += 1;
+        // https://github.com/bevyengine/bevy/pull/7817
+        let up_direction = out_direction.any_orthonormal_vector();
 
-                let state = if axes.is_empty() {
-                    BlockState::InPosition
+        let block = Block {
+            state: block_layout.state,
+            out_direction,
+        };
+        let tween = BlockTween::new(block.state);
+
+        let transform =
+            Transform::from_translation(translation).looking_to(out_direction, up_direction);
+
+        parent
+            .spawn(
+                // use an intermediate transform bundle so we keep the
+                // "origin" the same but can still animate the block itself
+                SpatialBundle {
+                    transform,
+                    ..default()
+                },
+            )
+            .with_children(|parent| {
+                let mut block_cmd = parent.spawn((
+                    BlockColor(index),
+                    MaterialMeshBundle {
+                        mesh: block_assets.mesh.clone(),
+                        material: block_assets.material(materials, colors[index]),
+                        // slightly smaller than 100% looks a little nicer
+                        transform: Transform::from_translation(tween.translation(animation))
+                            .with_scale(Vec3::splat(0.95 * cube_scale)),
+                        ..default()
+                    },
+                ));
+
+                if block_layout.out_direction.is_none() {
+                    block_cmd.insert(UnpickableBundle::default());
                 } else {
-                    BlockState::OutOfPlace
-                };
-
-                let out_direction = axes
-                    .choose(&mut rand::thread_rng())
-                    .copied()
-                    .unwrap_or(Vec3::Z);
-
-                // https://github.com/bevyengine/bevy/pull/7817
-                let up_direction = out_direction.any_orthonormal_vector();
-
-                let block = Block {
-                    state,
-                    out_direction,
-                };
-
-                let transform = Transform::from_translation(translation)
-                    .looking_to(out_direction, up_direction);
-
-                parent
-                    .spawn(
-                        // use an intermediate transform bundle so we keep the
-                        // "origin" the same but can still animate the block itself
-                        SpatialBundle {
-                            transform,
+                    let block_id = block_cmd
+                        .insert(BlockBundle {
+                            block,
+                            tween,
                             ..default()
-                        },
-                    )
-                    .with_children(|parent| {
-                        let mut block_cmd = parent.spawn((MaterialMeshBundle {
-                            mesh: cube_mesh.clone(),
-                            // TODO: reuse color materials maybe?
-                            // Something like ResMut<HashMap<Color, Handle<StandardMaterial>>?
-                            material: materials.add(StandardMaterial {
-                                metallic: 0.5,
-                                reflectance: 0.75,
-                                ..color.into()
-                            }),
-                            // slightly smaller than 100% looks a little nicer
-                            transform: Transform::from_scale(Vec3::splat(0.95 * cube_scale)),
-                            ..default()
-                        },));
-
-                        if axes.is_empty() {
-                            block_cmd.insert(UnpickableBundle::default());
-                        } else {
-                            let block_id =
-                                block_cmd.insert(BlockBundle { block, ..default() }).id();
+                        })
+                        .id();
 
-                            log::debug!("spawning block {block_id:?} at {transform:?}");
+                    log::debug!("spawning block {block_id:?} at {transform:?}");
 
-                            ids.push(block_id);
-                        }
-                    });
-            }
-        }
+                    ids.push(block_id);
+                }
+            });
     }
 
     ids
 }
 
-fn gen_combinations(cubes_per_axis: i16) -> impl Iterator<Item = i16> {
-    (1..=cubes_per_axis).map(|i| -i).chain(1..=cubes_per_axis)
+/// Hooks for `benches/spawn.rs`, which can only reach the public API.
+#[doc(hidden)]
+pub mod bench {
+    use bevy::ecs::system::RunSystemOnce;
+
+    use super::*;
+
+    /// How much a level takes up once spawned.
+    #[derive(Debug)]
+    pub struct LevelCounts {
+        pub entities: u32,
+        pub meshes: usize,
+        pub materials: usize,
+    }
+
+    /// An empty world with just what [`spawn_level`] needs.
+    pub fn world() -> World {
+        let mut world = World::new();
+        world.init_resource::<Assets<Mesh>>();
+        world.init_resource::<Assets<StandardMaterial>>();
+        world.init_resource::<BlockAssets>();
+        world
+    }
+
+    /// Spawn every block of a level with this many blocks per axis.
+    pub fn spawn_level(world: &mut World, block_count: i16) {
+        let layout = PuzzleLayout::from_seed(block_count, 0);
+
+        world.run_system_once(
+            move |mut commands: Commands,
+                  mut block_assets: ResMut<BlockAssets>,
+                  mut materials: ResMut<Assets<StandardMaterial>>| {
+                commands
+                    .spawn(SpatialBundle::default())
+                    .with_children(|parent| {
+                        spawn_blocks(
+                            parent,
+                            &mut block_assets,
+                            &mut materials,
+                            &layout,
+                            ColorPalette::Default,
+                            &PushAnimation::default(),
+                        );
+                    });
+            },
+        );
+    }
+
+    pub fn counts(world: &World) -> LevelCounts {
+        LevelCounts {
+            entities: world.entities().len(),
+            meshes: world.resource::<Assets<Mesh>>().len(),
+            materials: world.resource::<Assets<StandardMaterial>>().len(),
+        }
+    }
 }
@@ -0,0 +1,317 @@
+//! Short text codes that describe a puzzle completely, so players can share a
+//! specific cube with each other.
+//!
+//! A code looks like `1-4H-3FAVFQFG`: the generator version, the number
+//! of blocks per axis followed by the mode (`N`ormal or `H`ard), then the seed
+//! in base 32 with a check character on the end to catch typos.
+//!
+//! The check character works like Crockford's: the rest of the code, read as
+//! one base 32 number, modulo 37. Since that's prime, it catches any single
+//! wrong character and any swap of two neighbouring ones.
+
+use std::fmt;
+use std::str::FromStr;
+
+use super::graph::PropagateMode;
+use super::layout::PuzzleLayout;
+
+/// Which version of the puzzle generator codes are made for. Bump this whenever
+/// a change to the layout or the dependency tree would build a different puzzle
+/// from the same seed, so that old codes get turned away instead of quietly
+/// building something else.
+pub const GENERATOR_VERSION: u32 = 1;
+
+/// The biggest cube a code can ask for, and so the last level. Anything larger
+/// takes too long to build to be worth sharing.
+pub const MAX_BLOCK_COUNT: i16 = 20;
+
+/// Crockford's base 32, which leaves out letters that are easy to mix up, plus
+/// the extra symbols it uses for check values from 32 to 36.
+const ALPHABET: &[u8; 37] = b"0123456789ABCDEFGHJKMNPQRSTVWXYZ*~$=U";
+
+/// Everything needed to build the exact same puzzle again.
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub struct PuzzleCode {
+    pub block_count: i16,
+    pub seed: u64,
+    pub mode: PropagateMode,
+}
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum PuzzleCodeError {
+    /// The code isn't shaped like a puzzle code at all.
+    Malformed,
+    /// The code is shaped right, but a character was mistyped somewhere.
+    Mistyped,
+    /// The code was made by a different version of the game.
+    UnsupportedVersion(u32),
+    /// The cube is empty or too big to build.
+    InvalidSize(i16),
+}
+
+impl PuzzleCode {
+    pub fn new(layout: &PuzzleLayout, mode: PropagateMode) -> Self {
+        Self {
+            block_count: layout.block_count,
+            seed: layout.seed,
+            mode,
+        }
+    }
+
+    /// A fresh layout for this puzzle. The dependency tree follows from the seed
+    /// once the blocks are spawned.
+    pub fn layout(&self) -> PuzzleLayout {
+        PuzzleLayout {
+            shared_mode: Some(self.mode),
+            ..PuzzleLayout::from_seed(self.block_count, self.seed)
+        }
+    }
+}
+
+impl fmt::Display for PuzzleCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mode = match self.mode {
+            PropagateMode::Children => 'N',
+            PropagateMode::Ancestors => 'H',
+        };
+
+        let mut seed = Vec::new();
+        let mut remaining = self.seed;
+        loop {
+            seed.push(ALPHABET[(remaining % 32) as usize]);
+            remaining /= 32;
+            if remaining == 0 {
+                break;
+            }
+        }
+        seed.reverse();
+        let seed = String::from_utf8(seed).expect("alphabet is ASCII");
+
+        let code = format!("{GENERATOR_VERSION}-{}{mode}-{seed}", self.block_count);
+        let check = ALPHABET[checksum(&code).expect("generated codes are valid")] as char;
+
+        write!(f, "{code}{check}")
+    }
+}
+
+impl FromStr for PuzzleCode {
+    type Err = PuzzleCodeError;
+
+    /// Parse a code as leniently as possible: case and spaces don't matter, and
+    /// letters that look like digits are read as those digits.
+    fn from_str(code: &str) -> Result<Self, Self::Err> {
+        let code: String = code
+            .chars()
+            .filter(|c| !c.is_whitespace())
+            .map(|c| match c.to_ascii_uppercase() {
+                'O' => '0',
+                'I' | 'L' => '1',
+                c => c,
+            })
+            .collect();
+
+        // Every valid character is ASCII, and checking that up front means the
+        // code can be split by bytes from here on
+        if !code.is_ascii() {
+            return Err(PuzzleCodeError::Malformed);
+        }
+
+        let parts: Vec<&str> = code.split('-').collect();
+        let [version, size, seed] = parts[..] else {
+            return Err(PuzzleCodeError::Malformed);
+        };
+
+        // Check the version before anything else, since a different generator
+        // might have laid out the rest of the code differently too, check
+        // character included
+        let version: u32 = version.parse().map_err(|_| PuzzleCodeError::Malformed)?;
+        if version != GENERATOR_VERSION {
+            return Err(PuzzleCodeError::UnsupportedVersion(version));
+        }
+
+        if size.len() < 2 || seed.len() < 2 {
+            return Err(PuzzleCodeError::Malformed);
+        }
+
+        let (body, check) = code.split_at(code.len() - 1);
+        let expected = checksum(body).ok_or(PuzzleCodeError::Malformed)?;
+        if digit(check.as_bytes()[0]) != Some(expected as u64) {
+            return Err(PuzzleCodeError::Mistyped);
+        }
+
+        let (size, mode) = size.split_at(size.len() - 1);
+        let block_count: i16 = size.parse().map_err(|_| PuzzleCodeError::Malformed)?;
+        if !(1..=MAX_BLOCK_COUNT).contains(&block_count) {
+            return Err(PuzzleCodeError::InvalidSize(block_count));
+        }
+
+        let mode = match mode {
+            "N" => PropagateMode::Children,
+            "H" => PropagateMode::Ancestors,
+            _ => return Err(PuzzleCodeError::Malformed),
+        };
+
+        let seed = seed[..seed.len() - 1]
+            .bytes()
+            .try_fold(0u64, |seed, c| {
+                seed.checked_mul(32)?.checked_add(base32_digit(c)?)
+            })
+            .ok_or(PuzzleCodeError::Malformed)?;
+
+        Ok(Self {
+            block_count,
+            seed,
+            mode,
+        })
+    }
+}
+
+impl fmt::Display for PuzzleCodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::Malformed => write!(f, "not a puzzle code"),
+            Self::Mistyped => write!(f, "puzzle code has a typo"),
+            Self::UnsupportedVersion(version) => write!(
+                f,
+                "puzzle code is for generator version {version}, \
+                 but this game uses version {GENERATOR_VERSION}"
+            ),
+            Self::InvalidSize(size) => write!(
+                f,
+                "puzzle code has {size} blocks per axis, which isn't between 1 and \
+                 {MAX_BLOCK_COUNT}"
+            ),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleCodeError {}
+
+/// The value of a character, check symbols included, if it is one.
+fn digit(c: u8) -> Option<u64> {
+    ALPHABET
+        .iter()
+        .position(|&a| a == c)
+        .map(|value| value as u64)
+}
+
+/// The value of a base 32 character, leaving out the check symbols.
+fn base32_digit(c: u8) -> Option<u64> {
+    digit(c).filter(|&value| value < 32)
+}
+
+/// The check value for everything before it, ignoring dashes: all of it as one
+/// base 32 number, modulo 37.
+fn checksum(code: &str) -> Option<usize> {
+    code.bytes()
+        .filter(|&c| c != b'-')
+        .try_fold(0, |sum, c| Some((sum * 32 + base32_digit(c)?) % 37))
+        .map(|sum| sum as usize)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn round_trip() {
+        for (block_count, seed, mode) in [
+            (1, 0, PropagateMode::Children),
+            (4, 0xDEAD_BEEF, PropagateMode::Ancestors),
+            (MAX_BLOCK_COUNT, u64::MAX, PropagateMode::Children),
+        ] {
+            let code = PuzzleCode {
+                block_count,
+                seed,
+                mode,
+            };
+            let text = code.to_string();
+
+            assert_eq!(text.parse(), Ok(code), "{text}");
+            assert_eq!(text.to_lowercase().parse(), Ok(code), "{text}");
+        }
+    }
+
+    #[test]
+    fn reject_bad_codes() {
+        let code = PuzzleCode {
+            block_count: 3,
+            seed: 123_456_789,
+            mode: PropagateMode::Ancestors,
+        }
+        .to_string();
+
+        assert_eq!("".parse::<PuzzleCode>(), Err(PuzzleCodeError::Malformed));
+        assert_eq!(
+            "hello world".parse::<PuzzleCode>(),
+            Err(PuzzleCodeError::Malformed)
+        );
+
+        // Change one character of the seed, including the pair that used to be
+        // indistinguishable
+        for (from, to) in [(b'7', b'8'), (b'Z', b'0'), (b'0', b'Z')] {
+            let mut typo = code.clone().into_bytes();
+            let last_seed = typo.len() - 2;
+            typo[last_seed] = if typo[last_seed] == from { to } else { from };
+            let typo = String::from_utf8(typo).unwrap();
+            assert_eq!(typo.parse::<PuzzleCode>(), Err(PuzzleCodeError::Mistyped));
+        }
+
+        // Non-ASCII characters can't be split like the rest
+        assert_eq!(
+            "1-4H-ABé".parse::<PuzzleCode>(),
+            Err(PuzzleCodeError::Malformed)
+        );
+        assert_eq!(
+            format!("{code}é").parse::<PuzzleCode>(),
+            Err(PuzzleCodeError::Malformed)
+        );
+
+        // Codes from another generator version, whatever their check character
+        for check in ["0", "*", "?"] {
+            let other_version = format!("{}-3H-3NQK8N{check}", GENERATOR_VERSION + 1);
+            assert_eq!(
+                other_version.parse::<PuzzleCode>(),
+                Err(PuzzleCodeError::UnsupportedVersion(GENERATOR_VERSION + 1))
+            );
+        }
+
+        let body = format!("{GENERATOR_VERSION}-0N-3NQK8N");
+        let empty = format!("{body}{}", ALPHABET[checksum(&body).unwrap()] as char);
+        assert_eq!(
+            empty.parse::<PuzzleCode>(),
+            Err(PuzzleCodeError::InvalidSize(0))
+        );
+    }
+
+    #[test]
+    fn catch_swapped_neighbors() {
+        let code = PuzzleCode {
+            block_count: 12,
+            seed: 0x0123_4567_89AB_CDEF,
+            mode: PropagateMode::Children,
+        }
+        .to_string();
+
+        // Everything after the version, except for the check character itself
+        let bytes = code.as_bytes();
+        let body = code.find('-').unwrap() + 1..code.len() - 1;
+
+        for i in body.start..body.end - 1 {
+            let (a, b) = (bytes[i], bytes[i + 1]);
+            if a == b || a == b'-' || b == b'-' {
+                continue;
+            }
+
+            let mut swapped = bytes.to_vec();
+            swapped.swap(i, i + 1);
+            let swapped = String::from_utf8(swapped).unwrap();
+
+            assert_eq!(
+                swapped.parse::<PuzzleCode>(),
+                Err(PuzzleCodeError::Mistyped),
+                "{code} -> {swapped}"
+            );
+        }
+    }
+}
@@ -0,0 +1,159 @@
+//! An optional overlay that draws the dependency tree between blocks, as an
+//! assist for players and a debugging aid. Cascades also flash along the
+//! connection they travel over, whether or not the overlay is on.
+
+use bevy::prelude::*;
+
+use crate::settings::Settings;
+use crate::theme::CurrentTheme;
+use crate::GameState;
+
+use super::graph::{CascadeEvent, EntityGraph, PropagateMode};
+use super::layout::PuzzleLayout;
+use super::{Block, BlockState};
+
+pub struct ConnectionsPlugin;
+
+impl Plugin for ConnectionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, configure_gizmos).add_systems(
+            Update,
+            (
+                toggle_connections,
+                draw_connections
+                    .run_if(|settings: Res<Settings>| settings.gameplay.show_connections),
+                draw_cascade_flashes,
+            )
+                .chain()
+                .run_if(in_state(GameState::Playing)),
+        );
+    }
+}
+
+/// How long the arrowhead lines are, relative to the length of the arrow.
+const ARROWHEAD_SIZE: f32 = 0.2;
+
+/// The shortest a cascade flash lasts, so it's still visible with no delay.
+const MIN_FLASH_SECONDS: f32 = 0.2;
+
+/// How much of the connection the flash covers at once.
+const FLASH_LENGTH: f32 = 0.4;
+
+/// A flash travelling from the block that caused a cascade to the block it's
+/// about to reach.
+struct CascadeFlash {
+    cause: Entity,
+    effect: Entity,
+    timer: Timer,
+}
+
+fn configure_gizmos(mut config: ResMut<GizmoConfig>) {
+    // Draw over the blocks, otherwise most of the tree is hidden inside the cube
+    config.depth_bias = -1.0;
+}
+
+fn toggle_connections(mut settings: ResMut<Settings>, keyboard_input: Res<Input<KeyCode>>) {
+    if keyboard_input.just_pressed(KeyCode::G) {
+        settings.gameplay.show_connections = !settings.gameplay.show_connections;
+    }
+}
+
+/// Draw an arrow from every block to each block it would knock out when pushed in.
+fn draw_connections(
+    mut gizmos: Gizmos,
+    settings: Res<Settings>,
+    layout: Option<Res<PuzzleLayout>>,
+    graph: Query<&EntityGraph>,
+    blocks: Query<(&Block, &GlobalTransform)>,
+) {
+    let Ok(graph) = graph.get_single() else {
+        return;
+    };
+
+    let mode = PropagateMode::current(&settings, layout.as_deref());
+
+    for (parent, child) in graph.edges() {
+        let (from, to) = match mode {
+            PropagateMode::Children => (parent, child),
+            PropagateMode::Ancestors => (child, parent),
+        };
+
+        let (Ok((from_block, from_transform)), Ok((to_block, to_transform))) =
+            (blocks.get(from), blocks.get(to))
+        else {
+            continue;
+        };
+
+        let color = connection_color(from_block.state, to_block.state);
+
+        draw_arrow(
+            &mut gizmos,
+            from_transform.translation(),
+            to_transform.translation(),
+            color,
+        );
+    }
+}
+
+fn draw_cascade_flashes(
+    mut gizmos: Gizmos,
+    mut flashes: Local<Vec<CascadeFlash>>,
+    mut cascades: EventReader<CascadeEvent>,
+    time: Res<Time>,
+    settings: Res<Settings>,
+    theme: Res<CurrentTheme>,
+    blocks: Query<&GlobalTransform, With<Block>>,
+) {
+    // Arrive at the effect block right as the cascade does
+    let seconds = settings.gameplay.cascade_delay.max(MIN_FLASH_SECONDS);
+    flashes.extend(cascades.read().map(|cascade| CascadeFlash {
+        cause: cascade.cause,
+        effect: cascade.effect,
+        timer: Timer::from_seconds(seconds, TimerMode::Once),
+    }));
+
+    let color = theme.state_colors(settings.accessibility.palette).preview;
+
+    flashes.retain_mut(|flash| {
+        let (Ok(from), Ok(to)) = (blocks.get(flash.cause), blocks.get(flash.effect)) else {
+            // The level was replaced partway through
+            return false;
+        };
+
+        let head = flash.timer.tick(time.delta()).percent();
+        let tail = (head - FLASH_LENGTH).max(0.0);
+        let (from, to) = (from.translation(), to.translation());
+
+        gizmos.line(from.lerp(to, tail), from.lerp(to, head), color);
+
+        !flash.timer.finished()
+    });
+}
+
+fn connection_color(from: BlockState, to: BlockState) -> Color {
+    match (from, to) {
+        // Done, as long as nothing else gets pushed
+        (BlockState::InPosition, BlockState::InPosition) => Color::GREEN,
+        // Pushing `from` in now would undo `to`
+        (BlockState::OutOfPlace, BlockState::InPosition) => Color::RED,
+        // Either order works from here, nothing is at stake yet
+        (_, BlockState::OutOfPlace) => Color::YELLOW,
+    }
+}
+
+fn draw_arrow(gizmos: &mut Gizmos, from: Vec3, to: Vec3, color: Color) {
+    gizmos.line(from, to, color);
+
+    let direction = to - from;
+    // Any sideways direction works for the head, as long as it isn't parallel
+    let side = direction
+        .cross(Vec3::Y)
+        .try_normalize()
+        .unwrap_or_else(|| direction.normalize_or_zero().any_orthonormal_vector());
+
+    let back = -direction * ARROWHEAD_SIZE;
+    let spread = side * direction.length() * ARROWHEAD_SIZE * 0.5;
+
+    gizmos.line(to, to + back + spread, color);
+    gizmos.line(to, to + back - spread, color);
+}
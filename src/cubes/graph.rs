@@ -2,20 +2,27 @@ use bevy::log;
 use bevy::prelude::*;
 use bevy::utils::{HashMap, HashSet};
 use indextree::{Arena, NodeId};
+use rand::seq::SliceRandom;
+use rand::Rng;
 
-use crate::{tweak, GameState};
+use crate::settings::Settings;
+use crate::GameState;
 
-use super::activation::ToggleEvent;
+use super::layout::{puzzle_rng, PuzzleLayout, SeedStream};
+use super::tween::ToggleEvent;
 use super::{Block, BlockState};
 
 pub struct GraphPlugin;
 
 impl Plugin for GraphPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            propagate_block_toggles.run_if(in_state(GameState::Playing)),
-        );
+        app.add_event::<KnockOutEvent>()
+            .add_event::<CascadeEvent>()
+            .add_systems(
+                Update,
+                (propagate_block_toggles, fire_pending_cascades)
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
@@ -26,7 +33,10 @@ pub struct EntityGraph {
 }
 
 impl EntityGraph {
-    fn random_from_entities(entities: Vec<Entity>) -> Self {
+    /// Build a random tree out of all the entities, by shuffling them and then
+    /// attaching each one to a random entity that came before it. That can never
+    /// make a cycle, so it takes linear time no matter how many blocks there are.
+    fn random_from_entities(entities: Vec<Entity>, rng: &mut impl Rng) -> Self {
         let mut arena = Arena::new();
 
         let nodes: HashMap<Entity, NodeId> = entities
@@ -34,40 +44,101 @@ impl EntityGraph {
             .map(|&ent| (ent, arena.new_node(ent)))
             .collect();
 
-        let avg_children = 3;
-        let digraph = graphalgs::generate::random_digraph(
-            entities.len(),
-            entities.len() * (entities.len() - 1) / avg_children,
-        )
-        .expect("should always be a valid number of edges");
-
-        for (parent, child) in digraph {
-            let child = &entities[child];
-            let parent = &entities[parent];
-
-            if let Err(e1) = nodes[parent].checked_append(nodes[child], &mut arena) {
-                if let Err(e2) = nodes[child].checked_append(nodes[parent], &mut arena) {
-                    log::warn!("failed to create any edge {child:?} <-> {parent:?}: {e1}, {e2}");
-                }
-            }
+        let mut order = entities;
+        order.shuffle(rng);
+
+        for (index, child) in order.iter().enumerate().skip(1) {
+            // Sample a u32 rather than a usize, which is a different size on wasm
+            // and would build a different tree from the same seed
+            let parent = &order[rng.gen_range(0..index as u32) as usize];
+            nodes[parent].append(nodes[child], &mut arena);
         }
 
-        debug_assert!(
-            arena.count() == entities.len(),
-            "failed to add some blocks to the graph",
-        );
-        #[cfg(not(debug_assertions))]
-        if arena.count() != entities.len() {
-            log::error!("failed to add some blocks to the graph! this puzzle could be unsolvable");
+        if let Some(root) = order.first() {
+            log::debug!("built tree:\n{:?}", nodes[root].debug_pretty_print(&arena));
         }
 
-        // there might be a better way to find the root node, but this ought to work I think??
-        let head_node = nodes[&entities[0]].ancestors(&arena).last().unwrap();
-        log::debug!("built tree:\n{:?}", head_node.debug_pretty_print(&arena));
+        Self { arena, nodes }
+    }
+
+    /// Rebuild a tree from [`EntityGraph::parent_indices`], using a new set of entities.
+    fn from_parents(entities: &[Entity], parents: &[Option<usize>]) -> Self {
+        let mut arena = Arena::new();
+
+        let node_ids: Vec<NodeId> = entities.iter().map(|&ent| arena.new_node(ent)).collect();
+
+        for (child, parent) in parents.iter().enumerate() {
+            let Some(&parent) = parent.as_ref() else {
+                continue;
+            };
+
+            if let Err(err) = node_ids[parent].checked_append(node_ids[child], &mut arena) {
+                log::warn!("failed to restore edge {parent} -> {child}: {err}");
+            }
+        }
+
+        let nodes = entities.iter().copied().zip(node_ids).collect();
 
         Self { arena, nodes }
     }
 
+    /// The index of each entity's parent within `entities`, or `None` for the root.
+    fn parent_indices(&self, entities: &[Entity]) -> Vec<Option<usize>> {
+        let index_of: HashMap<Entity, usize> = entities
+            .iter()
+            .enumerate()
+            .map(|(index, &ent)| (ent, index))
+            .collect();
+
+        entities
+            .iter()
+            .map(|&ent| self.parent(ent).map(|parent| index_of[&parent]))
+            .collect()
+    }
+
+    /// The blocks that get knocked back out when the given block is pushed into place.
+    pub fn affected_by(&self, block: Entity, mode: PropagateMode) -> Vec<Entity> {
+        match mode {
+            PropagateMode::Children => self.children(block),
+            PropagateMode::Ancestors => self.parent(block).into_iter().collect(),
+        }
+    }
+
+    /// Every block that pushing the given block into place would end up knocking
+    /// out. Blocks that are already out of place pass the cascade along as if
+    /// they had been pushed in, just like [`propagate_block_toggles`] does.
+    pub fn knocked_out_by(
+        &self,
+        block: Entity,
+        mode: PropagateMode,
+        is_in_position: impl Fn(Entity) -> bool,
+    ) -> Vec<Entity> {
+        let mut knocked_out = Vec::new();
+        let mut visited = HashSet::new();
+        let mut to_visit = self.affected_by(block, mode);
+
+        while let Some(next) = to_visit.pop() {
+            if !visited.insert(next) {
+                continue;
+            }
+
+            if is_in_position(next) {
+                knocked_out.push(next);
+            } else {
+                to_visit.extend(self.affected_by(next, mode));
+            }
+        }
+
+        knocked_out
+    }
+
+    /// Every `(parent, child)` pair in the tree.
+    pub fn edges(&self) -> impl Iterator<Item = (Entity, Entity)> + '_ {
+        self.nodes
+            .keys()
+            .filter_map(|&child| self.parent(child).map(|parent| (parent, child)))
+    }
+
     fn children(&self, block: Entity) -> Vec<Entity> {
         let Some(node) = self.nodes.get(&block) else {
             return Vec::new();
@@ -90,13 +161,46 @@ impl EntityGraph {
     }
 }
 
-/// Spawn a random graph using the given entities
-pub fn build_graph(In(blocks): In<Vec<Entity>>, mut commands: Commands) {
+/// Spawn the graph for the given entities, reusing the layout's tree if it already has
+/// one, or generating a random one (and remembering it in the layout) if not.
+pub fn build_graph(
+    In((blocks, mut layout)): In<(Vec<Entity>, PuzzleLayout)>,
+    mut commands: Commands,
+) {
     // TODO: this perhaps could be a non-piped system that runs when blocks are Added
-    commands.spawn(EntityGraph::random_from_entities(blocks));
+    let graph = match &layout.parents {
+        Some(parents) => EntityGraph::from_parents(&blocks, parents),
+        None => {
+            let mut rng = puzzle_rng(layout.seed, SeedStream::Tree);
+            let graph = EntityGraph::random_from_entities(blocks.clone(), &mut rng);
+            layout.parents = Some(graph.parent_indices(&blocks));
+            graph
+        }
+    };
+
+    commands.spawn(graph);
+    commands.insert_resource(layout);
+}
+
+/// A block was knocked out of position by a cascade, rather than by the player.
+#[derive(Event)]
+pub struct KnockOutEvent {
+    pub block: Entity,
 }
 
-#[derive(Debug, Copy, Clone)]
+/// A cascade is on its way from one block to another, and will reach it after
+/// the cascade delay.
+#[derive(Event)]
+pub struct CascadeEvent {
+    pub cause: Entity,
+    pub effect: Entity,
+}
+
+/// A cascade that hasn't reached this block yet.
+#[derive(Component, Deref, DerefMut)]
+pub struct PendingCascade(Timer);
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
 pub enum PropagateMode {
     /// Easier difficulty, since a mistake along the way is more likely to end up
     /// costing less.
@@ -106,38 +210,56 @@ pub enum PropagateMode {
 }
 
 impl PropagateMode {
-    fn new(hard_mode: bool) -> Self {
+    pub fn new(hard_mode: bool) -> Self {
         if hard_mode {
             Self::Ancestors
         } else {
             Self::Children
         }
     }
+
+    /// The mode the current puzzle is played in: whatever its shared code asked
+    /// for, or else the hard mode setting.
+    pub fn current(settings: &Settings, layout: Option<&PuzzleLayout>) -> Self {
+        layout
+            .and_then(|layout| layout.shared_mode)
+            .unwrap_or_else(|| Self::new(settings.gameplay.hard_mode))
+    }
+
+    /// The key for a short, player-facing name of the mode.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Children => "mode.normal",
+            Self::Ancestors => "mode.hard",
+        }
+    }
 }
 
 pub fn propagate_block_toggles(
+    mut commands: Commands,
     mut events: ParamSet<(EventReader<ToggleEvent>, EventWriter<ToggleEvent>)>,
-    mut blocks: Query<(Entity, &mut Block)>,
+    mut blocks: Query<(&mut Block, Has<PendingCascade>)>,
+    mut knocked_out: EventWriter<KnockOutEvent>,
+    mut cascades: EventWriter<CascadeEvent>,
     graph: Query<&EntityGraph>,
+    settings: Res<Settings>,
+    layout: Option<Res<PuzzleLayout>>,
 ) {
     let graph = graph.single();
 
-    let mode = PropagateMode::new(tweak!(false));
+    let mode = PropagateMode::current(&settings, layout.as_deref());
+    let delay = settings.gameplay.cascade_delay;
 
-    let mut to_toggle = HashSet::new();
+    // Which block each affected block is being reached from
+    let mut to_toggle = HashMap::new();
 
     let mut event_reader = events.p0();
 
     for toggled in event_reader.read() {
         // moving a block out of place doesn't affect anything
         if toggled.state == BlockState::InPosition {
-            let affected_blocks = match mode {
-                PropagateMode::Children => graph.children(toggled.block),
-                PropagateMode::Ancestors => graph.parent(toggled.block).into_iter().collect(),
-            };
-
-            for child in affected_blocks {
-                to_toggle.insert(child);
+            for child in graph.affected_by(toggled.block, mode) {
+                to_toggle.insert(child, toggled.block);
             }
         }
     }
@@ -146,28 +268,73 @@ pub fn propagate_block_toggles(
         log::debug!("propagating toggles to {to_toggle:?}");
     }
 
+    let mut toggles = events.p1();
+
     // idk if this is a very efficient way to iterate, but it seems to work okay.
-    for entity in to_toggle {
-        if let Ok((_, mut block)) = blocks.get_mut(entity) {
-            // only move blocks out of position, not into position
-            if block.state == BlockState::InPosition {
-                block.state.toggle();
-            } else {
-                // HACK: basically, we're recursing into this system again on
-                // the next tick to force this block to get updated again. It
-                // isn't really in position, but it will be treated like it is
-                // and propagate to its parent/child blocks as well.
-                events.p1().send(ToggleEvent {
-                    state: BlockState::InPosition,
-                    block: entity,
-                });
-
-                // It might work better to have an intermediate event type, that
-                // only gets sent when
-            }
-        } else {
+    for (entity, cause) in to_toggle {
+        let Ok((mut block, pending)) = blocks.get_mut(entity) else {
             log::warn!("couldn't find {entity:?} to propagate toggle");
+            continue;
+        };
+
+        // Another cascade is already on its way here, so let it keep its timer
+        // rather than starting the countdown over
+        if pending {
+            continue;
         }
+
+        cascades.send(CascadeEvent {
+            cause,
+            effect: entity,
+        });
+
+        if delay > 0.0 {
+            commands
+                .entity(entity)
+                .insert(PendingCascade(Timer::from_seconds(delay, TimerMode::Once)));
+        } else {
+            reach_block(entity, &mut block, &mut knocked_out, &mut toggles);
+        }
+    }
+}
+
+/// Carry on any cascades whose delay is up.
+fn fire_pending_cascades(
+    mut commands: Commands,
+    time: Res<Time>,
+    mut blocks: Query<(Entity, &mut Block, &mut PendingCascade)>,
+    mut knocked_out: EventWriter<KnockOutEvent>,
+    mut toggles: EventWriter<ToggleEvent>,
+) {
+    for (entity, mut block, mut pending) in &mut blocks {
+        if pending.tick(time.delta()).finished() {
+            commands.entity(entity).remove::<PendingCascade>();
+            reach_block(entity, &mut block, &mut knocked_out, &mut toggles);
+        }
+    }
+}
+
+/// A cascade has reached this block, so knock it out, or pass the cascade along
+/// if it's already out.
+fn reach_block(
+    entity: Entity,
+    block: &mut Block,
+    knocked_out: &mut EventWriter<KnockOutEvent>,
+    toggles: &mut EventWriter<ToggleEvent>,
+) {
+    // only move blocks out of position, not into position
+    if block.state == BlockState::InPosition {
+        block.state.toggle();
+        knocked_out.send(KnockOutEvent { block: entity });
+    } else {
+        // HACK: basically, we're recursing into the propagation system again on
+        // the next tick to force this block to get updated again. It isn't
+        // really in position, but it will be treated like it is and propagate
+        // to its parent/child blocks as well.
+        toggles.send(ToggleEvent {
+            state: BlockState::InPosition,
+            block: entity,
+        });
     }
 }
 
@@ -179,6 +346,8 @@ mod tests {
     // generation is likely to fail in general.
     #[test]
     fn generate_tree() {
+        let mut rng = puzzle_rng(0, SeedStream::Tree);
+
         // it's random, so let's do it a lot
         for _ in 0..100 {
             for entity_count in 8..120 {
@@ -188,8 +357,69 @@ mod tests {
                     .map(|_| app.world.spawn_empty().id())
                     .collect();
 
-                let _ = EntityGraph::random_from_entities(ents);
+                let graph = EntityGraph::random_from_entities(ents.clone(), &mut rng);
+                let roots = ents.iter().filter(|&&ent| graph.parent(ent).is_none());
+                assert_eq!(roots.count(), 1);
             }
         }
+
+        // Roughly the shell of a cube with 40 blocks per axis, which should still be quick
+        // even in debug builds
+        for entity_count in [1_000, 10_000, 40_000] {
+            let mut app = App::new();
+
+            let ents: Vec<Entity> = (0..entity_count)
+                .map(|_| app.world.spawn_empty().id())
+                .collect();
+
+            let start = std::time::Instant::now();
+            let graph = EntityGraph::random_from_entities(ents.clone(), &mut rng);
+            let elapsed = start.elapsed();
+
+            assert_eq!(graph.arena.count(), entity_count);
+            assert!(
+                elapsed < std::time::Duration::from_secs(1),
+                "took {elapsed:?} to build a tree of {entity_count}"
+            );
+        }
+    }
+
+    #[test]
+    fn restore_tree_from_parents() {
+        let mut app = App::new();
+
+        let ents: Vec<Entity> = (0..50).map(|_| app.world.spawn_empty().id()).collect();
+        let graph = EntityGraph::random_from_entities(ents.clone(), &mut rand::thread_rng());
+        let parents = graph.parent_indices(&ents);
+
+        // A restarted level gets brand new entities for the same blocks
+        let new_ents: Vec<Entity> = (0..50).map(|_| app.world.spawn_empty().id()).collect();
+        let restored = EntityGraph::from_parents(&new_ents, &parents);
+
+        assert_eq!(restored.parent_indices(&new_ents), parents);
+        assert_eq!(restored.arena.count(), new_ents.len());
+    }
+
+    #[test]
+    fn knock_out_cascade() {
+        let mut app = App::new();
+
+        // 0 -> 1 -> 2 -> 3, with 1 -> 4 as well
+        let ents: Vec<Entity> = (0..5).map(|_| app.world.spawn_empty().id()).collect();
+        let graph = EntityGraph::from_parents(&ents, &[None, Some(0), Some(1), Some(2), Some(1)]);
+
+        // 1 is out of place, so the cascade passes through it to 2 and 4, but
+        // stops at 2 since it gets knocked out itself.
+        let in_position = |ent: Entity| ent != ents[1];
+
+        let mut knocked_out = graph.knocked_out_by(ents[0], PropagateMode::Children, in_position);
+        knocked_out.sort();
+        assert_eq!(knocked_out, vec![ents[2], ents[4]]);
+
+        let knocked_out = graph.knocked_out_by(ents[3], PropagateMode::Ancestors, in_position);
+        assert_eq!(knocked_out, vec![ents[2]]);
+
+        let knocked_out = graph.knocked_out_by(ents[2], PropagateMode::Ancestors, in_position);
+        assert_eq!(knocked_out, vec![ents[0]]);
     }
 }
@@ -10,9 +10,13 @@ use bevy_mod_picking::highlight::{
 use bevy_mod_picking::picking_core::Pickable;
 use bevy_mod_picking::PickableBundle;
 
+use crate::settings::Settings;
+use crate::theme::CurrentTheme;
 use crate::GameState;
 
-use super::activation::ToggleTimer;
+use super::graph::{EntityGraph, PropagateMode};
+use super::layout::PuzzleLayout;
+use super::tween::BlockTween;
 use super::{Block, BlockState};
 
 pub struct HighlightPlugin;
@@ -42,11 +46,21 @@ impl Plugin for HighlightPlugin {
             ))
             .add_systems(
                 Update,
-                set_highlighted_outlines.run_if(in_state(GameState::Playing)),
+                (preview_knock_outs, set_highlighted_outlines)
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
             );
     }
 }
 
+/// Draws attention to a block with an outline, even when it isn't hovered.
+#[derive(Component)]
+pub struct Spotlight;
+
+/// Marks a block that would get knocked out by pushing the hovered block.
+#[derive(Component)]
+struct KnockOutPreview;
+
 #[derive(Bundle)]
 pub struct HighlightableBundle {
     pub pickable: PickableBundle,
@@ -96,28 +110,83 @@ impl Default for UnpickableBundle {
     }
 }
 
+/// Mark every block that pushing the hovered one would knock out, including any
+/// knock-on effects further down the cascade.
+fn preview_knock_outs(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    assets: Res<Assets<Highlight>>,
+    blocks: Query<(Entity, &Block, &Handle<Highlight>, Has<KnockOutPreview>)>,
+    graph: Query<&EntityGraph>,
+    layout: Option<Res<PuzzleLayout>>,
+) {
+    let mode = PropagateMode::current(&settings, layout.as_deref());
+
+    let hovered = blocks.iter().find(|(_, block, highlight, _)| {
+        block.state == BlockState::OutOfPlace
+            && matches!(assets.get(*highlight), Some(Highlight::Hovered))
+    });
+
+    let knocked_out = match (hovered, graph.get_single()) {
+        (Some((entity, ..)), Ok(graph)) if settings.gameplay.preview_knock_outs => graph
+            .knocked_out_by(entity, mode, |block| {
+                blocks
+                    .get(block)
+                    .is_ok_and(|(_, block, ..)| block.state == BlockState::InPosition)
+            }),
+        _ => Vec::new(),
+    };
+
+    for (entity, _, _, previewed) in &blocks {
+        let should_preview = knocked_out.contains(&entity);
+
+        if previewed && !should_preview {
+            commands.entity(entity).remove::<KnockOutPreview>();
+        } else if !previewed && should_preview {
+            commands.entity(entity).insert(KnockOutPreview);
+        }
+    }
+}
+
+/// Outline widths, so a block's state doesn't depend on telling colors apart.
+const OUT_OF_PLACE_WIDTH: f32 = 8.0;
+const IN_POSITION_WIDTH: f32 = 3.0;
+const BUSY_WIDTH: f32 = 5.0;
+const PREVIEW_WIDTH: f32 = 6.0;
+const SPOTLIGHT_WIDTH: f32 = 10.0;
+
 fn set_highlighted_outlines(
+    settings: Res<Settings>,
+    theme: Res<CurrentTheme>,
     assets: Res<Assets<Highlight>>,
-    mut outlinables: Query<(&Handle<Highlight>, &mut OutlineVolume, &Block, &ToggleTimer)>,
+    mut outlinables: Query<(
+        &Handle<Highlight>,
+        &mut OutlineVolume,
+        &Block,
+        &BlockTween,
+        Has<Spotlight>,
+        Has<KnockOutPreview>,
+    )>,
 ) {
-    for (highlight, mut outline, block, timer) in &mut outlinables {
-        let Some(highlight) = assets.get(highlight) else {
-            outline.visible = false;
-            continue;
-        };
+    let colors = theme.state_colors(settings.accessibility.palette);
 
+    for (highlight, mut outline, block, tween, spotlight, preview) in &mut outlinables {
         outline.visible = true;
 
-        outline.colour = match highlight {
-            Highlight::Pressed => Color::BLUE,
-            Highlight::Hovered | Highlight::Selected if !timer.paused() && !timer.finished() => {
-                Color::BLUE
+        let highlight = assets.get(highlight);
+
+        (outline.colour, outline.width) = match highlight {
+            Some(Highlight::Pressed) => (colors.busy, BUSY_WIDTH),
+            Some(Highlight::Hovered | Highlight::Selected) if !tween.is_settled() => {
+                (colors.busy, BUSY_WIDTH)
             }
-            Highlight::Hovered => match block.state {
-                BlockState::OutOfPlace => Color::RED,
-                BlockState::InPosition => Color::GREEN,
+            Some(Highlight::Hovered) => match block.state {
+                BlockState::OutOfPlace => (colors.out_of_place, OUT_OF_PLACE_WIDTH),
+                BlockState::InPosition => (colors.in_position, IN_POSITION_WIDTH),
             },
-            Highlight::Selected => {
+            Some(Highlight::Selected) | None if preview => (colors.preview, PREVIEW_WIDTH),
+            Some(Highlight::Selected) | None if spotlight => (colors.spotlight, SPOTLIGHT_WIDTH),
+            Some(Highlight::Selected) | None => {
                 outline.visible = false;
                 continue;
             }
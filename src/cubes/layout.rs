@@ -0,0 +1,135 @@
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use rand_chacha::ChaCha8Rng;
+
+use super::graph::PropagateMode;
+use super::BlockState;
+
+/// The generator behind everything decided from a puzzle's seed. Unlike
+/// `StdRng`, its output is fixed, so the same seed keeps building the same
+/// puzzle across rand versions and platforms.
+pub type PuzzleRng = ChaCha8Rng;
+
+/// The different things decided from a puzzle's seed. Each one draws from its
+/// own stream, so that e.g. the colors aren't tied to which way the blocks point.
+#[derive(Clone, Copy, Debug)]
+pub enum SeedStream {
+    Directions,
+    Colors,
+    Tree,
+}
+
+/// A generator for one of the things decided from a puzzle's seed.
+pub fn puzzle_rng(seed: u64, stream: SeedStream) -> PuzzleRng {
+    let mut rng = PuzzleRng::seed_from_u64(seed);
+    rng.set_stream(stream as u64);
+    rng
+}
+
+/// Everything that was randomly decided about the current level, so that the
+/// exact same puzzle can be built again when restarting.
+#[derive(Resource, Clone, Debug)]
+pub struct PuzzleLayout {
+    pub block_count: i16,
+    /// Everything else about the layout, including block colors, is derived from this.
+    pub seed: u64,
+    /// One entry for every block in the cube, in the order they get spawned.
+    pub blocks: Vec<BlockLayout>,
+    /// The dependency tree, as the index of each pushable block's parent (or
+    /// `None` for the root). Only known once the tree has been generated.
+    pub parents: Option<Vec<Option<usize>>>,
+    /// The mode a puzzle loaded from a shared code has to be played in. Other
+    /// puzzles follow the hard mode setting.
+    pub shared_mode: Option<PropagateMode>,
+}
+
+#[derive(Clone, Debug)]
+pub struct BlockLayout {
+    /// Which way the block points, or `None` for blocks on the inside of the
+    /// cube that can't be moved at all.
+    pub out_direction: Option<Vec3>,
+    pub state: BlockState,
+}
+
+impl PuzzleLayout {
+    /// Whether the puzzle came from a shared code, rather than the level progression.
+    pub fn is_shared(&self) -> bool {
+        self.shared_mode.is_some()
+    }
+
+    pub fn random(block_count: i16) -> Self {
+        Self::from_seed(block_count, rand::random())
+    }
+
+    pub fn from_seed(block_count: i16, seed: u64) -> Self {
+        let mut rng = puzzle_rng(seed, SeedStream::Directions);
+
+        let blocks = block_positions(block_count)
+            .map(|position| {
+                let axes = block_axes(position, block_count);
+
+                let state = if axes.is_empty() {
+                    BlockState::InPosition
+                } else {
+                    BlockState::OutOfPlace
+                };
+
+                BlockLayout {
+                    out_direction: axes.choose(&mut rng).copied(),
+                    state,
+                }
+            })
+            .collect();
+
+        Self {
+            block_count,
+            seed,
+            blocks,
+            parents: None,
+            shared_mode: None,
+        }
+    }
+}
+
+/// Every block position in a cube, as signed, 1-based coordinates along each axis.
+pub fn block_positions(cubes_per_axis: i16) -> impl Iterator<Item = (i16, i16, i16)> {
+    gen_combinations(cubes_per_axis).flat_map(move |i| {
+        gen_combinations(cubes_per_axis)
+            .flat_map(move |j| gen_combinations(cubes_per_axis).map(move |k| (i, j, k)))
+    })
+}
+
+/// A block position on a contiguous, 0-based grid, which makes it easy to find
+/// the blocks next to it.
+pub fn grid_position((i, j, k): (i16, i16, i16), cubes_per_axis: i16) -> IVec3 {
+    // Block coordinates skip zero
+    let grid = |c: i16| {
+        i32::from(if c < 0 {
+            c + cubes_per_axis
+        } else {
+            c + cubes_per_axis - 1
+        })
+    };
+    IVec3::new(grid(i), grid(j), grid(k))
+}
+
+/// The outward facing axes a block at this position could be pushed along. Blocks
+/// on the inside of the cube don't have any.
+pub fn block_axes((i, j, k): (i16, i16, i16), cubes_per_axis: i16) -> Vec<Vec3> {
+    let mut axes = Vec::new();
+    if i.abs() == cubes_per_axis {
+        axes.push(Vec3::X * f32::from(i.signum()));
+    }
+    if j.abs() == cubes_per_axis {
+        axes.push(Vec3::Y * f32::from(j.signum()));
+    }
+    if k.abs() == cubes_per_axis {
+        axes.push(Vec3::Z * f32::from(k.signum()));
+    }
+    axes
+}
+
+fn gen_combinations(cubes_per_axis: i16) -> impl Iterator<Item = i16> {
+    (1..=cubes_per_axis).map(|i| -i).chain(1..=cubes_per_axis)
+}
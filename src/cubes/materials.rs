@@ -0,0 +1,96 @@
+//! Meshes and materials shared between blocks, so that big cubes don't need
+//! thousands of identical assets.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::theme::CurrentTheme;
+
+/// Everything about a block's material besides its color.
+#[derive(Deserialize, Clone, Copy, Debug)]
+#[serde(default)]
+pub struct BlockMaterial {
+    pub metallic: f32,
+    pub reflectance: f32,
+    pub perceptual_roughness: f32,
+}
+
+impl Default for BlockMaterial {
+    fn default() -> Self {
+        Self {
+            metallic: 0.5,
+            reflectance: 0.75,
+            perceptual_roughness: 0.5,
+        }
+    }
+}
+
+impl BlockMaterial {
+    fn apply(&self, material: &mut StandardMaterial) {
+        material.metallic = self.metallic;
+        material.reflectance = self.reflectance;
+        material.perceptual_roughness = self.perceptual_roughness;
+    }
+}
+
+#[derive(Resource)]
+pub struct BlockAssets {
+    pub mesh: Handle<Mesh>,
+    params: BlockMaterial,
+    /// Keyed by the color as packed RGBA, since [`Color`] isn't hashable.
+    materials: HashMap<u32, Handle<StandardMaterial>>,
+}
+
+impl FromWorld for BlockAssets {
+    fn from_world(world: &mut World) -> Self {
+        let mut meshes = world.resource_mut::<Assets<Mesh>>();
+
+        Self {
+            mesh: meshes.add(shape::Cube::default().into()),
+            params: BlockMaterial::default(),
+            materials: HashMap::new(),
+        }
+    }
+}
+
+impl BlockAssets {
+    /// Get the material for this color, creating it if no block has used it yet.
+    pub fn material(
+        &mut self,
+        materials: &mut Assets<StandardMaterial>,
+        color: Color,
+    ) -> Handle<StandardMaterial> {
+        self.materials
+            .entry(color.as_rgba_u32())
+            .or_insert_with(|| {
+                let mut material: StandardMaterial = color.into();
+                self.params.apply(&mut material);
+
+                materials.add(material)
+            })
+            .clone()
+    }
+
+    /// Forget every material, so that ones the next level doesn't use get freed
+    /// along with the old blocks.
+    pub fn clear_materials(&mut self) {
+        self.materials.clear();
+    }
+}
+
+/// Restyle every block material to match a newly applied theme.
+pub(super) fn update_block_materials(
+    theme: Res<CurrentTheme>,
+    mut block_assets: ResMut<BlockAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+) {
+    block_assets.params = theme.block_material;
+
+    for handle in block_assets.materials.values() {
+        if let Some(material) = materials.get_mut(handle) {
+            block_assets.params.apply(material);
+        }
+    }
+}
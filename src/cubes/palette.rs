@@ -0,0 +1,392 @@
+//! Color schemes for the blocks and their outlines, including ones that stay
+//! readable for players with color vision deficiencies.
+
+use std::collections::HashMap;
+
+use bevy::prelude::*;
+use rand::seq::SliceRandom;
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+
+use super::layout::{
+    block_positions, grid_position, puzzle_rng, PuzzleLayout, PuzzleRng, SeedStream,
+};
+use super::materials::BlockAssets;
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            recolor_blocks.run_if(resource_changed::<Settings>()),
+        );
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ColorPalette {
+    /// As many colors as the cube needs, spread out as evenly as possible.
+    #[default]
+    Default,
+    /// Avoids telling colors apart by red versus green (the most common kind).
+    Deuteranopia,
+    /// Like deuteranopia, but also avoids relying on how bright reds are.
+    Protanopia,
+    /// Avoids telling colors apart by blue versus yellow.
+    Tritanopia,
+    /// A few very bright and very dark colors.
+    HighContrast,
+}
+
+/// Which of the [`PuzzleLayout`]'s blocks this is, to look up its color in
+/// whichever palette is active.
+#[derive(Component, Clone, Copy, Debug)]
+pub struct BlockColor(pub usize);
+
+/// The most colors to generate for the default palette. Past this, colors get
+/// too close together to tell apart at a glance anyway.
+const MAX_GENERATED_COLORS: usize = 40;
+
+/// How many random colors to pick the generated ones from.
+const CANDIDATE_COLORS: usize = 1024;
+
+/// The outline colors used to show what's going on with a block.
+#[derive(Deserialize, Clone, Copy, Debug)]
+pub struct StateColors {
+    pub out_of_place: Color,
+    pub in_position: Color,
+    /// The block is being pressed, or is still moving.
+    pub busy: Color,
+    /// The block would get knocked out by pushing the hovered one.
+    pub preview: Color,
+    /// The tutorial wants the player to look at this block.
+    pub spotlight: Color,
+}
+
+// Based on the Okabe-Ito palette, which was designed to be told apart with any
+// kind of color vision.
+const DEUTERANOPIA_COLORS: &[Color] = &[
+    Color::rgb(0.90, 0.62, 0.0),
+    Color::rgb(0.34, 0.71, 0.91),
+    Color::rgb(0.0, 0.62, 0.45),
+    Color::rgb(0.94, 0.89, 0.26),
+    Color::rgb(0.0, 0.45, 0.70),
+    Color::rgb(0.84, 0.37, 0.0),
+    Color::rgb(0.80, 0.47, 0.65),
+    Color::rgb(0.1, 0.1, 0.1),
+    Color::rgb(0.6, 0.6, 0.6),
+];
+
+// Reds look much darker without red cones, so leave out the vermillion
+const PROTANOPIA_COLORS: &[Color] = &[
+    Color::rgb(0.90, 0.62, 0.0),
+    Color::rgb(0.34, 0.71, 0.91),
+    Color::rgb(0.0, 0.62, 0.45),
+    Color::rgb(0.94, 0.89, 0.26),
+    Color::rgb(0.0, 0.45, 0.70),
+    Color::rgb(0.80, 0.47, 0.65),
+    Color::rgb(0.1, 0.1, 0.1),
+    Color::rgb(0.6, 0.6, 0.6),
+    Color::rgb(0.95, 0.95, 0.95),
+];
+
+// Reds, teals and greys, which don't depend on telling blue from yellow
+const TRITANOPIA_COLORS: &[Color] = &[
+    Color::rgb(0.89, 0.10, 0.11),
+    Color::rgb(1.0, 0.50, 0.62),
+    Color::rgb(0.0, 0.63, 0.63),
+    Color::rgb(0.12, 0.44, 0.44),
+    Color::rgb(0.55, 0.0, 0.0),
+    Color::rgb(0.95, 0.95, 0.95),
+    Color::rgb(0.33, 0.33, 0.33),
+    Color::rgb(0.1, 0.1, 0.1),
+];
+
+const HIGH_CONTRAST_COLORS: &[Color] = &[
+    Color::WHITE,
+    Color::BLACK,
+    Color::YELLOW,
+    Color::rgb(0.0, 0.3, 1.0),
+    Color::FUCHSIA,
+    Color::CYAN,
+];
+
+impl ColorPalette {
+    pub const ALL: [ColorPalette; 5] = [
+        ColorPalette::Default,
+        ColorPalette::Deuteranopia,
+        ColorPalette::Protanopia,
+        ColorPalette::Tritanopia,
+        ColorPalette::HighContrast,
+    ];
+
+    fn fixed_colors(self) -> Option<&'static [Color]> {
+        match self {
+            Self::Default => None,
+            Self::Deuteranopia => Some(DEUTERANOPIA_COLORS),
+            Self::Protanopia => Some(PROTANOPIA_COLORS),
+            Self::Tritanopia => Some(TRITANOPIA_COLORS),
+            Self::HighContrast => Some(HIGH_CONTRAST_COLORS),
+        }
+    }
+
+    /// The color of every block in the layout, in the same order. Blocks on the
+    /// outside that share a face never get the same color, and the same seed
+    /// always gives the same colors.
+    pub fn block_colors(self, layout: &PuzzleLayout) -> Vec<Color> {
+        let mut rng = puzzle_rng(layout.seed, SeedStream::Colors);
+
+        let colors = match self.fixed_colors() {
+            Some(colors) => {
+                let mut colors = colors.to_vec();
+                colors.shuffle(&mut rng);
+                colors
+            }
+            None => generate_colors(layout.blocks.len().clamp(1, MAX_GENERATED_COLORS), &mut rng),
+        };
+
+        assign_colors(&colors, layout)
+    }
+
+    pub fn state_colors(self) -> StateColors {
+        match self {
+            Self::Default => StateColors {
+                out_of_place: Color::RED,
+                in_position: Color::GREEN,
+                busy: Color::BLUE,
+                preview: Color::ORANGE,
+                spotlight: Color::GOLD,
+            },
+            Self::Deuteranopia | Self::Protanopia => StateColors {
+                out_of_place: Color::rgb(0.90, 0.62, 0.0),
+                in_position: Color::rgb(0.34, 0.71, 0.91),
+                busy: Color::WHITE,
+                preview: Color::rgb(0.80, 0.47, 0.65),
+                spotlight: Color::rgb(0.94, 0.89, 0.26),
+            },
+            Self::Tritanopia => StateColors {
+                out_of_place: Color::rgb(0.89, 0.10, 0.11),
+                in_position: Color::rgb(0.0, 0.63, 0.63),
+                busy: Color::WHITE,
+                preview: Color::rgb(1.0, 0.50, 0.62),
+                spotlight: Color::rgb(0.95, 0.95, 0.95),
+            },
+            Self::HighContrast => StateColors {
+                out_of_place: Color::FUCHSIA,
+                in_position: Color::WHITE,
+                busy: Color::CYAN,
+                preview: Color::YELLOW,
+                spotlight: Color::LIME_GREEN,
+            },
+        }
+    }
+
+    /// The key for the palette's name in the settings screen.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Default => "settings.palette_default",
+            Self::Deuteranopia => "settings.palette_deuteranopia",
+            Self::Protanopia => "settings.palette_protanopia",
+            Self::Tritanopia => "settings.palette_tritanopia",
+            Self::HighContrast => "settings.palette_high_contrast",
+        }
+    }
+}
+
+/// Convert a color to OKLab, where straight-line distance roughly matches how
+/// different two colors look.
+fn to_oklab(color: Color) -> Vec3 {
+    let [r, g, b, _] = color.as_linear_rgba_f32();
+
+    let l = 0.412_221_46 * r + 0.536_332_55 * g + 0.051_445_995 * b;
+    let m = 0.211_903_5 * r + 0.680_699_5 * g + 0.107_396_96 * b;
+    let s = 0.088_302_46 * r + 0.281_718_85 * g + 0.629_978_7 * b;
+
+    let (l, m, s) = (l.cbrt(), m.cbrt(), s.cbrt());
+
+    Vec3::new(
+        0.210_454_26 * l + 0.793_617_8 * m - 0.004_072_047 * s,
+        1.977_998_5 * l - 2.428_592_2 * m + 0.450_593_7 * s,
+        0.025_904_037 * l + 0.782_771_77 * m - 0.808_675_77 * s,
+    )
+}
+
+/// Pick `count` colors that are as far apart from each other as possible.
+fn generate_colors(count: usize, rng: &mut PuzzleRng) -> Vec<Color> {
+    let candidates: Vec<(Color, Vec3)> = std::iter::repeat_with(|| {
+        let color = Color::rgb(rng.gen(), rng.gen(), rng.gen());
+        (color, to_oklab(color))
+    })
+    // Very dark or very light blocks lose their shading and look flat
+    .filter(|(_, lab)| (0.45..=0.9).contains(&lab.x))
+    .take(CANDIDATE_COLORS)
+    .collect();
+
+    // Repeatedly take whichever candidate is furthest from everything taken so far
+    let mut distances = vec![f32::INFINITY; candidates.len()];
+    let mut colors = Vec::with_capacity(count);
+
+    for _ in 0..count {
+        let Some((next, _)) = distances
+            .iter()
+            .enumerate()
+            .max_by(|(_, a), (_, b)| a.total_cmp(b))
+        else {
+            break;
+        };
+
+        let (color, lab) = candidates[next];
+        colors.push(color);
+
+        for (distance, (_, other)) in distances.iter_mut().zip(&candidates) {
+            *distance = distance.min(lab.distance(*other));
+        }
+    }
+
+    colors
+}
+
+/// Give each block one of `colors`, so that no two blocks sharing a face are
+/// the same color and neighbors are as different as possible.
+fn assign_colors(colors: &[Color], layout: &PuzzleLayout) -> Vec<Color> {
+    let labs: Vec<Vec3> = colors.iter().map(|&color| to_oklab(color)).collect();
+
+    let grid: Vec<IVec3> = block_positions(layout.block_count)
+        .map(|position| grid_position(position, layout.block_count))
+        .collect();
+    let positions: HashMap<IVec3, usize> = grid
+        .iter()
+        .enumerate()
+        .map(|(index, &position)| (position, index))
+        .collect();
+
+    let mut assigned: Vec<Option<usize>> = vec![None; layout.blocks.len()];
+    let mut uses = vec![0_usize; colors.len()];
+
+    // Color the outside first, where the player can push blocks and compare them
+    // most easily, then fill in the inside around it
+    let (outside, inside): (Vec<usize>, Vec<usize>) =
+        (0..layout.blocks.len()).partition(|&index| layout.blocks[index].out_direction.is_some());
+
+    for index in outside.into_iter().chain(inside) {
+        let position = grid[index];
+        let neighbors: Vec<usize> = [IVec3::X, IVec3::Y, IVec3::Z]
+            .into_iter()
+            .flat_map(|axis| [position + axis, position - axis])
+            .filter_map(|neighbor| positions.get(&neighbor))
+            .filter_map(|&neighbor| assigned[neighbor])
+            .collect();
+
+        let closest_neighbor = |color: usize| {
+            neighbors
+                .iter()
+                .map(|&neighbor| labs[color].distance(labs[neighbor]))
+                .fold(f32::INFINITY, f32::min)
+        };
+
+        // Spread colors evenly over the cube, then keep neighbors far apart
+        let preference = |&a: &usize, &b: &usize| {
+            uses[a]
+                .cmp(&uses[b])
+                .then(closest_neighbor(b).total_cmp(&closest_neighbor(a)))
+        };
+
+        // Deep inside a big cube, a small palette can run out of colors that no
+        // neighbor has yet, so settle for the least bad one there
+        let best = (0..colors.len())
+            .filter(|color| !neighbors.contains(color))
+            .min_by(preference)
+            .or_else(|| (0..colors.len()).min_by(preference))
+            .unwrap_or_default();
+
+        uses[best] += 1;
+        assigned[index] = Some(best);
+    }
+
+    assigned
+        .into_iter()
+        .map(|color| colors[color.unwrap_or_default()])
+        .collect()
+}
+
+fn recolor_blocks(
+    settings: Res<Settings>,
+    layout: Option<Res<PuzzleLayout>>,
+    mut block_assets: ResMut<BlockAssets>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut blocks: Query<(&BlockColor, &mut Handle<StandardMaterial>)>,
+    mut current_palette: Local<ColorPalette>,
+) {
+    let palette = settings.accessibility.palette;
+    if palette == *current_palette {
+        return;
+    }
+    *current_palette = palette;
+
+    let Some(layout) = layout else {
+        return;
+    };
+
+    let colors = palette.block_colors(&layout);
+
+    // Materials are shared, so swap to the new color's material rather than
+    // changing the old one
+    for (&BlockColor(index), mut material) in &mut blocks {
+        *material = block_assets.material(&mut materials, colors[index]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn neighbors_never_share_colors() {
+        for block_count in 1..=6 {
+            let layout = PuzzleLayout::from_seed(block_count, 1234);
+            let positions: Vec<IVec3> = block_positions(block_count)
+                .map(|position| grid_position(position, block_count))
+                .collect();
+
+            for palette in ColorPalette::ALL {
+                let colors = palette.block_colors(&layout);
+                let regenerated = PuzzleLayout::from_seed(block_count, 1234);
+                assert_eq!(colors, palette.block_colors(&regenerated));
+
+                // The inside shows through wherever blocks are out of place, so
+                // it should be colored like the rest rather than all the same
+                if block_count >= 4 {
+                    let mut inside: Vec<u32> = layout
+                        .blocks
+                        .iter()
+                        .zip(&colors)
+                        .filter(|(block, _)| block.out_direction.is_none())
+                        .map(|(_, color)| color.as_rgba_u32())
+                        .collect();
+                    inside.sort_unstable();
+                    inside.dedup();
+                    assert!(inside.len() > 1, "{palette:?} at size {block_count}");
+                }
+
+                for (a, block_a) in layout.blocks.iter().enumerate() {
+                    for (b, block_b) in layout.blocks.iter().enumerate() {
+                        let visible =
+                            block_a.out_direction.is_some() && block_b.out_direction.is_some();
+                        let offset = (positions[a] - positions[b]).abs();
+                        let touching = offset.x + offset.y + offset.z == 1;
+
+                        if visible && touching {
+                            assert_ne!(
+                                colors[a], colors[b],
+                                "{palette:?} at size {block_count}: {a} and {b} match"
+                            );
+                        }
+                    }
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,288 @@
+//! Slides blocks between their in and out positions.
+
+use std::f32::consts::PI;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::settings::Settings;
+use crate::GameState;
+
+use super::{Block, BlockState};
+
+pub struct TweenPlugin;
+
+impl Plugin for TweenPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<PushAnimation>()
+            .add_event::<ToggleEvent>()
+            .add_systems(Update, tween_blocks.run_if(in_state(GameState::Playing)));
+    }
+}
+
+/// How blocks slide in and out. Themes can swap this out for a different feel.
+#[derive(Resource, Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct PushAnimation {
+    /// How far a block sticks out of the cube when it's out of place.
+    pub distance: f32,
+    /// Seconds to slide all the way in or out, at normal animation speed.
+    pub duration: f32,
+    pub easing: Easing,
+}
+
+/// The shortest a push can take. Anything quicker looks instant anyway, and zero
+/// or less would stop the tween from ever settling.
+const MIN_DURATION: f32 = 0.01;
+
+impl PushAnimation {
+    /// Fix up values that would break the tween, e.g. from a hand-edited theme.
+    pub fn sanitized(self) -> Self {
+        Self {
+            duration: self.duration.max(MIN_DURATION),
+            ..self
+        }
+    }
+}
+
+impl Default for PushAnimation {
+    fn default() -> Self {
+        Self {
+            distance: 0.75,
+            duration: 0.3,
+            easing: Easing::Linear,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Debug, Default)]
+pub enum Easing {
+    #[default]
+    Linear,
+    /// Starts fast and slows down towards the end.
+    EaseOut,
+    /// Wobbles back and forth around the end before settling.
+    Spring,
+    /// Goes a little past the end, then comes back.
+    Overshoot,
+}
+
+impl Easing {
+    /// Map linear progress from 0.0 to 1.0 onto how far along the motion is.
+    /// Every curve starts exactly at 0.0 and ends exactly at 1.0, but some go
+    /// past the end along the way.
+    pub fn apply(self, t: f32) -> f32 {
+        if t >= 1.0 {
+            return 1.0;
+        }
+        if t <= 0.0 {
+            return 0.0;
+        }
+
+        match self {
+            Self::Linear => t,
+            Self::EaseOut => 1.0 - (1.0 - t).powi(3),
+            Self::Spring => 1.0 - (1.0 - t) * (-4.0 * t).exp() * (5.0 * PI * t).cos(),
+            Self::Overshoot => {
+                const BACK: f32 = 1.70158;
+                1.0 + (BACK + 1.0) * (t - 1.0).powi(3) + BACK * (t - 1.0).powi(2)
+            }
+        }
+    }
+}
+
+/// Indicates a block has finished its trajectory to the given state.
+#[derive(Event)]
+pub struct ToggleEvent {
+    pub state: BlockState,
+    pub block: Entity,
+}
+
+/// Where a block is along its path, from 0.0 when it's all the way in to 1.0
+/// when it's all the way out. Changing direction partway through just heads
+/// back from wherever the block currently is.
+///
+/// Progress always moves at a constant rate, so the time left until the block
+/// settles doesn't depend on the easing curve. The curve only changes where the
+/// block is drawn along the way.
+#[derive(Component, Debug)]
+pub struct BlockTween {
+    progress: f32,
+    target: f32,
+    /// Where the current leg of the trip started, as progress and as eased
+    /// position, so that turning around doesn't make the block jump.
+    leg_start: f32,
+    leg_start_position: f32,
+}
+
+impl Default for BlockTween {
+    fn default() -> Self {
+        Self::new(BlockState::default())
+    }
+}
+
+impl BlockTween {
+    /// A tween that's already settled in the given state.
+    pub fn new(state: BlockState) -> Self {
+        let progress = Self::progress_for(state);
+        Self {
+            progress,
+            target: progress,
+            leg_start: progress,
+            leg_start_position: progress,
+        }
+    }
+
+    fn progress_for(state: BlockState) -> f32 {
+        match state {
+            BlockState::InPosition => 0.0,
+            BlockState::OutOfPlace => 1.0,
+        }
+    }
+
+    /// Whether the block has stopped moving.
+    pub fn is_settled(&self) -> bool {
+        self.progress == self.target
+    }
+
+    /// How far out the block is drawn, from 0.0 to 1.0 (give or take overshoot).
+    fn position(&self, easing: Easing) -> f32 {
+        let leg_length = (self.target - self.leg_start).abs();
+        let t = if leg_length > 0.0 {
+            1.0 - (self.target - self.progress).abs() / leg_length
+        } else {
+            1.0
+        };
+
+        self.leg_start_position + (self.target - self.leg_start_position) * easing.apply(t)
+    }
+
+    /// The block's offset from its in position, in its parent's space.
+    pub fn translation(&self, animation: &PushAnimation) -> Vec3 {
+        // Blocks face outward, so "forward" is out of the cube
+        animation.distance * self.position(animation.easing) * Vec3::NEG_Z
+    }
+
+    /// Move towards `state` by `step` (out of a full trip of 1.0), returning
+    /// whether the block settled in this step.
+    fn advance(&mut self, state: BlockState, step: f32, easing: Easing) -> bool {
+        let target = Self::progress_for(state);
+        if target != self.target {
+            self.leg_start_position = self.position(easing);
+            self.leg_start = self.progress;
+            self.target = target;
+        }
+
+        if self.is_settled() {
+            return false;
+        }
+
+        let remaining = self.target - self.progress;
+        if remaining.abs() <= step {
+            self.progress = self.target;
+            true
+        } else {
+            self.progress += step.copysign(remaining);
+            false
+        }
+    }
+}
+
+fn tween_blocks(
+    time: Res<Time>,
+    settings: Res<Settings>,
+    animation: Res<PushAnimation>,
+    mut blocks: Query<(Entity, &Block, &mut BlockTween, &mut Transform)>,
+    mut events: EventWriter<ToggleEvent>,
+) {
+    // The speed setting scales the logical progress, so blocks settle (and
+    // cascades carry on) sooner, not just look faster
+    let step = time.delta_seconds() * settings.gameplay.animation_speed / animation.duration;
+
+    for (entity, block, mut tween, mut transform) in &mut blocks {
+        // Avoid triggering change detection on blocks that are sitting still
+        if tween.is_settled() && tween.target == BlockTween::progress_for(block.state) {
+            continue;
+        }
+
+        let settled = tween.advance(block.state, step, animation.easing);
+        transform.translation = tween.translation(&animation);
+
+        if settled {
+            events.send(ToggleEvent {
+                state: block.state,
+                block: entity,
+            });
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_mid_flight() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseOut,
+            Easing::Spring,
+            Easing::Overshoot,
+        ] {
+            let mut tween = BlockTween::new(BlockState::InPosition);
+            assert!(!tween.advance(BlockState::OutOfPlace, 0.5, easing));
+            let halfway = tween.position(easing);
+
+            // Turning around doesn't move the block by itself
+            assert!(!tween.advance(BlockState::InPosition, 0.0, easing));
+            assert!(
+                (tween.position(easing) - halfway).abs() < 1e-6,
+                "{easing:?}"
+            );
+
+            // and the way back is exactly as long as the way out was
+            assert!(!tween.advance(BlockState::InPosition, 0.25, easing));
+            assert!(tween.advance(BlockState::InPosition, 0.25, easing));
+            assert_eq!(tween.position(easing), 0.0, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn easing_endpoints() {
+        for easing in [
+            Easing::Linear,
+            Easing::EaseOut,
+            Easing::Spring,
+            Easing::Overshoot,
+        ] {
+            assert_eq!(easing.apply(0.0), 0.0, "{easing:?}");
+            assert_eq!(easing.apply(1.0), 1.0, "{easing:?}");
+        }
+    }
+
+    #[test]
+    fn sanitize_duration() {
+        for duration in [0.0, -1.0, f32::NAN] {
+            let animation = PushAnimation {
+                duration,
+                ..default()
+            }
+            .sanitized();
+            assert!(animation.duration > 0.0, "{duration}");
+        }
+    }
+
+    #[test]
+    fn settles_once() {
+        let mut tween = BlockTween::new(BlockState::OutOfPlace);
+        assert!(tween.is_settled());
+
+        let settled: Vec<bool> = (0..10)
+            .map(|_| tween.advance(BlockState::InPosition, 0.3, Easing::Linear))
+            .collect();
+
+        assert_eq!(settled.iter().filter(|&&settled| settled).count(), 1);
+        assert_eq!(settled.iter().position(|&settled| settled), Some(3));
+        assert!(tween.is_settled());
+    }
+}
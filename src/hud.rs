@@ -0,0 +1,181 @@
+use bevy::prelude::*;
+
+use crate::cubes::{
+    Block, BlockCount, BlockState, PropagateMode, PuzzleCode, PuzzleLayout, ToggleEvent,
+};
+use crate::level::LevelStats;
+use crate::loading::FontAssets;
+use crate::localization::{Localization, LocalizedText};
+use crate::menu::IGNORE_PICKING;
+use crate::progress::format_time;
+use crate::settings::Settings;
+use crate::GameState;
+
+pub struct HudPlugin;
+
+/// This plugin shows how the current level is going while it's being played.
+impl Plugin for HudPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Playing), setup_hud)
+            .add_systems(
+                Update,
+                (update_remaining_blocks, update_hud_stats).run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(OnExit(GameState::Playing), cleanup_hud);
+    }
+}
+
+#[derive(Component)]
+struct HudRoot;
+
+/// Which stat a line of HUD text shows.
+#[derive(Component, Clone, Copy)]
+enum HudText {
+    Level,
+    Remaining,
+    Moves,
+    Time,
+    Mode,
+    Code,
+}
+
+fn setup_hud(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    localization: Res<Localization>,
+    block_count: Res<BlockCount>,
+    stats: Res<LevelStats>,
+    settings: Res<Settings>,
+    layout: Option<Res<PuzzleLayout>>,
+    blocks: Query<&Block>,
+) {
+    let text_style = TextStyle {
+        font_size: 26.0,
+        ..font_assets.button_style()
+    };
+
+    let lines = [
+        (HudText::Level, level_text(&block_count)),
+        (HudText::Remaining, remaining_text(&blocks)),
+        (HudText::Moves, moves_text(&stats)),
+        (HudText::Time, time_text(&stats)),
+        (
+            HudText::Mode,
+            mode_text(&settings, layout.as_deref(), &localization),
+        ),
+        (HudText::Code, code_text(layout.as_deref(), &settings)),
+    ];
+
+    commands
+        .spawn((
+            HudRoot,
+            IGNORE_PICKING,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    top: Val::Px(10.0),
+                    left: Val::Px(10.0),
+                    padding: UiRect::all(Val::Px(8.0)),
+                    flex_direction: FlexDirection::Column,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.3).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for (line, text) in lines {
+                parent.spawn((line, IGNORE_PICKING, text.bundle(text_style.clone())));
+            }
+        });
+}
+
+fn level_text(block_count: &BlockCount) -> LocalizedText {
+    LocalizedText::with_args("hud.level", [block_count.0])
+}
+
+fn remaining_text(blocks: &Query<&Block>) -> LocalizedText {
+    let remaining = blocks
+        .iter()
+        .filter(|block| block.state == BlockState::OutOfPlace)
+        .count();
+
+    LocalizedText::with_args("hud.remaining", [remaining])
+}
+
+fn moves_text(stats: &LevelStats) -> LocalizedText {
+    LocalizedText::with_args("hud.moves", [stats.moves])
+}
+
+fn time_text(stats: &LevelStats) -> LocalizedText {
+    LocalizedText::with_args("hud.time", [format_time(stats.elapsed)])
+}
+
+fn mode_text(
+    settings: &Settings,
+    layout: Option<&PuzzleLayout>,
+    localization: &Localization,
+) -> LocalizedText {
+    let mode = PropagateMode::current(settings, layout);
+    LocalizedText::with_args("hud.mode", [localization.get(mode.label())])
+}
+
+/// The code for the puzzle being played, so it can be shared. The mode is part
+/// of the code, so it follows the current setting unless the puzzle was shared.
+fn code_text(layout: Option<&PuzzleLayout>, settings: &Settings) -> LocalizedText {
+    let code = layout.map_or_else(String::new, |layout| {
+        let mode = PropagateMode::current(settings, Some(layout));
+        PuzzleCode::new(layout, mode).to_string()
+    });
+
+    LocalizedText::with_args("hud.code", [code])
+}
+
+fn update_remaining_blocks(
+    mut toggles: EventReader<ToggleEvent>,
+    blocks: Query<&Block>,
+    new_blocks: Query<(), Added<Block>>,
+    mut texts: Query<(&mut LocalizedText, &HudText)>,
+) {
+    // A new cube can be spawned after the HUD, e.g. when restarting or loading a code
+    if toggles.read().count() == 0 && new_blocks.is_empty() {
+        return;
+    }
+
+    for (mut text, line) in &mut texts {
+        if let HudText::Remaining = line {
+            *text = remaining_text(&blocks);
+        }
+    }
+}
+
+fn update_hud_stats(
+    stats: Res<LevelStats>,
+    block_count: Res<BlockCount>,
+    settings: Res<Settings>,
+    localization: Res<Localization>,
+    layout: Option<Res<PuzzleLayout>>,
+    mut texts: Query<(&mut LocalizedText, &HudText)>,
+) {
+    for (mut text, line) in &mut texts {
+        let value = match line {
+            HudText::Level => level_text(&block_count),
+            HudText::Moves => moves_text(&stats),
+            HudText::Time => time_text(&stats),
+            HudText::Mode => mode_text(&settings, layout.as_deref(), &localization),
+            HudText::Code => code_text(layout.as_deref(), &settings),
+            HudText::Remaining => continue,
+        };
+
+        // Only touch the text when it actually changes, to avoid re-laying it out
+        if *text != value {
+            *text = value;
+        }
+    }
+}
+
+fn cleanup_hud(mut commands: Commands, hud: Query<Entity, With<HudRoot>>) {
+    for entity in &hud {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -1,195 +1,193 @@
-use bevy::{log, prelude::*};
-
-#[cfg(not(target_family = "wasm"))]
-use bevy::app::AppExit;
+use bevy::prelude::*;
 
 use crate::cubes::{
-    Block, BlockCount, BlockState, CubeFrame, EntityGraph, ToggleEvent, ToggleTimer,
+    Block, BlockCount, BlockState, BlockTween, CubeFrame, EntityGraph, KnockOutEvent, NewLevel,
+    PendingCascade, PropagateMode, PushEvent, PuzzleCode, PuzzleLayout, ToggleEvent,
+    MAX_BLOCK_COUNT,
 };
-use crate::loading::FontAssets;
-use crate::menu::ButtonColors;
+use crate::progress::{NewBests, Progress};
+use crate::settings::Settings;
 use crate::GameState;
 
+mod celebration;
+mod summary;
+
 pub struct LevelPlugin;
 
 impl Plugin for LevelPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(
-            Update,
-            (
-                win_condition.run_if(in_state(GameState::Playing)),
-                click_continue.run_if(in_state(GameState::Reset)),
-            ),
-        )
-        .add_systems(OnEnter(GameState::Reset), (reset_level, setup_buttons))
-        .add_systems(OnExit(GameState::Reset), cleanup_continue);
-
-        // "quitting" kinda just crashes the app in wasm, let's not do that
-        #[cfg(not(target_family = "wasm"))]
-        app.add_systems(Update, click_quit.run_if(in_state(GameState::Reset)));
+        app.init_resource::<LevelStats>()
+            .init_resource::<LevelResult>()
+            .add_plugins((celebration::CelebrationPlugin, summary::SummaryPlugin))
+            .add_systems(
+                Update,
+                (
+                    reset_level_stats,
+                    (track_level_stats, win_condition).run_if(in_state(GameState::Playing)),
+                ),
+            )
+            .add_systems(
+                OnEnter(GameState::Reset),
+                record_progress.before(summary::setup_summary),
+            );
     }
 }
 
-fn win_condition(
-    blocks: Query<(&Block, &AnimationPlayer)>,
-    mut events: EventReader<ToggleEvent>,
-    mut next_state: ResMut<NextState<GameState>>,
-) {
-    if events.read().any(|evt| evt.state != BlockState::InPosition) {
-        return;
-    }
-
-    if blocks.is_empty() {
-        return;
-    }
+/// How the player is doing on the level currently being played.
+#[derive(Resource, Default, Debug)]
+pub struct LevelStats {
+    /// Blocks pushed by the player, not counting ones moved by propagation.
+    pub moves: u32,
+    /// Seconds spent playing, not counting time spent paused.
+    pub elapsed: f32,
+    /// Blocks that were pushed back out by a cascade from another block.
+    pub knocked_out: u32,
+    /// Pushes made with the connections overlay or push previews switched on.
+    pub hints: u32,
+    /// The fewest moves the level can be solved in. Pushing the blocks in the
+    /// right order never knocks anything out, so it's one move per block.
+    pub par: u32,
+}
 
-    if blocks.iter().all(|(block, player)| {
-        // player.is_finished() would be nice but doesn't seem to work in reverse here.
-        // (maybe should file a bug report for ath?)
-        //
-        // < 0.01 should always work, since the player will stop animating *after* the
-        // time reaches zero, so it should normally be a little bit less than zero.
-        if block.state == BlockState::InPosition {
-            log::debug!("seek time: {:?}", player.seek_time());
-            player.seek_time() < 0.01
-        } else {
-            false
-        }
-    }) {
-        next_state.set(GameState::Reset);
-    }
+/// The final stats of the level that was just completed, along with which
+/// records they beat.
+#[derive(Resource, Default, Debug)]
+pub struct LevelResult {
+    pub level: i16,
+    pub moves: u32,
+    pub elapsed: f32,
+    pub knocked_out: u32,
+    pub hints: u32,
+    pub par: u32,
+    pub new_bests: NewBests,
+    /// The code to share this exact puzzle with.
+    pub code: Option<PuzzleCode>,
+    /// Whether the puzzle came from a shared code, rather than the level progression.
+    pub shared: bool,
 }
 
-#[cfg(debug_assertions)]
-pub(crate) fn skip_level(
-    mut next_state: ResMut<NextState<GameState>>,
-    keyboard_input: Res<Input<KeyCode>>,
-) {
-    if keyboard_input.just_pressed(KeyCode::N) {
-        next_state.set(GameState::Reset);
+impl LevelResult {
+    /// The level to continue on to, if there is one. Shared puzzles aren't part
+    /// of the progression, so they don't lead anywhere.
+    pub fn next_level(&self) -> Option<i16> {
+        (!self.shared && self.level < MAX_BLOCK_COUNT).then_some(self.level + 1)
     }
 }
 
-#[derive(Component)]
-struct ContinueButton;
-
-#[derive(Component)]
-struct QuitButton;
-
-fn setup_buttons(
-    mut commands: Commands,
-    font_assets: Res<FontAssets>,
-    button_colors: Res<ButtonColors>,
+fn reset_level_stats(
+    mut stats: ResMut<LevelStats>,
+    new_cube: Query<(), Added<CubeFrame>>,
+    blocks: Query<(), With<Block>>,
 ) {
-    let style = Style {
-        margin: UiRect::all(Val::Auto),
-        justify_content: JustifyContent::Center,
-        align_items: AlignItems::Center,
-        ..default()
-    };
-
-    commands
-        .spawn(NodeBundle {
-            style: Style {
-                width: Val::Px(350.0),
-                height: Val::Px(125.0),
-                flex_direction: FlexDirection::Column,
-                ..style.clone()
-            },
+    if !new_cube.is_empty() {
+        *stats = LevelStats {
+            par: blocks.iter().count() as u32,
             ..default()
-        })
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "Nice work! Keep going?",
-                font_assets.text_style(),
-            ));
-
-            parent
-                .spawn(NodeBundle {
-                    style: Style {
-                        width: Val::Px(350.0),
-                        height: Val::Px(50.0),
-                        flex_direction: FlexDirection::Row,
-                        ..style
-                    },
-                    ..default()
-                })
-                .with_children(|parent| spawn_buttons(parent, font_assets, button_colors));
-        });
+        };
+    }
 }
 
-fn spawn_buttons(
-    parent: &mut ChildBuilder,
-    font_assets: Res<FontAssets>,
-    button_colors: Res<ButtonColors>,
+fn track_level_stats(
+    mut stats: ResMut<LevelStats>,
+    mut pushes: EventReader<PushEvent>,
+    mut knock_outs: EventReader<KnockOutEvent>,
+    time: Res<Time>,
+    settings: Res<Settings>,
 ) {
-    let button = ButtonBundle {
-        style: Style {
-            width: Val::Px(150.0),
-            height: Val::Px(50.0),
-            margin: UiRect::all(Val::Auto),
-            justify_content: JustifyContent::Center,
-            align_items: AlignItems::Center,
-            ..Default::default()
-        },
-        background_color: button_colors.normal.into(),
-        ..Default::default()
-    };
-
-    parent
-        .spawn((ContinueButton, button.clone()))
-        .with_children(|parent| {
-            parent.spawn(TextBundle::from_section(
-                "Continue",
-                font_assets.button_style(),
-            ));
-        });
-
-    #[cfg(not(target_family = "wasm"))]
-    parent.spawn((QuitButton, button)).with_children(|parent| {
-        parent.spawn(TextBundle::from_section("Quit", font_assets.button_style()));
-    });
+    let pushed = pushes.read().count() as u32;
+    let assisted = settings.gameplay.show_connections || settings.gameplay.preview_knock_outs;
+
+    stats.elapsed += time.delta_seconds();
+    stats.moves += pushed;
+    stats.knocked_out += knock_outs.read().count() as u32;
+    if assisted {
+        stats.hints += pushed;
+    }
 }
 
-type IsGameEntity = Or<(With<CubeFrame>, With<EntityGraph>)>;
-
-fn reset_level(
-    game_entities: Query<Entity, IsGameEntity>,
-    mut commands: Commands,
-    mut block_count: ResMut<BlockCount>,
+fn win_condition(
+    blocks: Query<(&Block, &BlockTween)>,
+    pending: Query<(), With<PendingCascade>>,
+    graph: Query<&EntityGraph>,
+    settings: Res<Settings>,
+    layout: Option<Res<PuzzleLayout>>,
+    mut events: EventReader<ToggleEvent>,
+    mut next_state: ResMut<NextState<GameState>>,
 ) {
-    for entity in &game_entities {
-        commands.entity(entity).despawn_recursive();
+    let toggled: Vec<_> = events.read().collect();
+    if toggled
+        .iter()
+        .any(|evt| evt.state != BlockState::InPosition)
+    {
+        return;
     }
 
-    block_count.0 += 1;
-}
+    if blocks.is_empty() || !pending.is_empty() {
+        return;
+    }
 
-fn click_continue(
-    mut state: ResMut<NextState<GameState>>,
-    mut continue_interaction: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
-) {
-    for interaction in &mut continue_interaction {
-        if let Interaction::Pressed = *interaction {
-            state.set(GameState::Playing);
+    // A block that just got pushed in might still be about to knock something
+    // out, if the cascade hasn't been propagated yet
+    let mode = PropagateMode::current(&settings, layout.as_deref());
+    if let Ok(graph) = graph.get_single() {
+        if toggled
+            .iter()
+            .any(|evt| !graph.affected_by(evt.block, mode).is_empty())
+        {
+            return;
         }
     }
+
+    if blocks
+        .iter()
+        .all(|(block, tween)| block.state == BlockState::InPosition && tween.is_settled())
+    {
+        next_state.set(GameState::Celebrating);
+    }
 }
 
-#[cfg(not(target_family = "wasm"))]
-fn click_quit(
-    mut quit_interaction: Query<&Interaction, (Changed<Interaction>, With<QuitButton>)>,
-    mut quit: EventWriter<AppExit>,
+/// Jump straight to the next level, without the level counting as solved.
+#[cfg(debug_assertions)]
+pub(crate) fn skip_level(
+    mut block_count: ResMut<BlockCount>,
+    mut new_level: EventWriter<NewLevel>,
+    keyboard_input: Res<Input<KeyCode>>,
 ) {
-    for interaction in &mut quit_interaction {
-        if let Interaction::Pressed = *interaction {
-            quit.send(AppExit);
-        }
+    if keyboard_input.just_pressed(KeyCode::N) && block_count.0 < MAX_BLOCK_COUNT {
+        block_count.0 += 1;
+        new_level.send(NewLevel);
     }
 }
 
-fn cleanup_continue(mut commands: Commands, ui_nodes: Query<Entity, With<Node>>) {
-    for node in &ui_nodes {
-        commands.entity(node).despawn_recursive();
-    }
+fn record_progress(
+    mut progress: ResMut<Progress>,
+    mut result: ResMut<LevelResult>,
+    block_count: Res<BlockCount>,
+    stats: Res<LevelStats>,
+    layout: Option<Res<PuzzleLayout>>,
+    settings: Res<Settings>,
+) {
+    let layout = layout.as_deref();
+    let mode = PropagateMode::current(&settings, layout);
+
+    // Shared puzzles aren't the level's usual ones, so they don't count towards
+    // unlocking levels or setting records
+    let shared = layout.is_some_and(PuzzleLayout::is_shared);
+    let new_bests = if shared {
+        NewBests::default()
+    } else {
+        progress.record(block_count.0, stats.elapsed, stats.moves)
+    };
+
+    *result = LevelResult {
+        level: block_count.0,
+        moves: stats.moves,
+        elapsed: stats.elapsed,
+        knocked_out: stats.knocked_out,
+        hints: stats.hints,
+        par: stats.par,
+        new_bests,
+        code: layout.map(|layout| PuzzleCode::new(layout, mode)),
+        shared,
+    };
 }
@@ -0,0 +1,268 @@
+//! A short celebration between finishing a level and the results panel. The
+//! cube spins, the blocks ripple outwards and confetti falls, until it runs out
+//! or the player skips it.
+
+use std::f32::consts::{PI, TAU};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::cubes::{Block, BlockTween, CubeFrame, PushAnimation};
+use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::menu::IGNORE_PICKING;
+use crate::settings::{GameplaySettings, Settings};
+use crate::GameState;
+
+pub struct CelebrationPlugin;
+
+impl Plugin for CelebrationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Celebrating), start_celebration)
+            .add_systems(
+                Update,
+                (spin_cube, wave_blocks, fall_confetti, finish_celebration)
+                    .run_if(in_state(GameState::Celebrating))
+                    .run_if(resource_exists::<Celebration>()),
+            )
+            .add_systems(OnExit(GameState::Celebrating), end_celebration);
+    }
+}
+
+/// How far blocks move out at the peak of the wave, on top of where they are.
+const WAVE_DISTANCE: f32 = 0.4;
+
+/// How much of the celebration it takes the wave to reach the outermost blocks.
+/// Each block's own pulse takes up the rest.
+const WAVE_SPREAD: f32 = 0.5;
+
+const CONFETTI_COUNT: usize = 120;
+
+/// Downwards acceleration of confetti, in percent of the screen per second squared.
+const CONFETTI_GRAVITY: f32 = 60.0;
+
+const CONFETTI_COLORS: &[Color] = &[
+    Color::rgb(0.95, 0.3, 0.35),
+    Color::rgb(0.98, 0.8, 0.2),
+    Color::rgb(0.3, 0.75, 0.95),
+    Color::rgb(0.45, 0.85, 0.45),
+    Color::rgb(0.8, 0.5, 0.95),
+    Color::WHITE,
+];
+
+#[derive(Resource)]
+struct Celebration {
+    timer: Timer,
+    /// How the cube was turned before it started spinning.
+    start_rotation: Quat,
+}
+
+impl Celebration {
+    /// How far along the celebration is, from 0.0 to 1.0.
+    fn progress(&self) -> f32 {
+        self.timer.percent()
+    }
+}
+
+#[derive(Component)]
+struct CelebrationRoot;
+
+#[derive(Component)]
+struct Confetti {
+    /// Position and velocity are in percent of the screen, to fit any window size.
+    position: Vec2,
+    velocity: Vec2,
+    /// Offset for the side-to-side flutter, so the pieces don't move in lockstep.
+    phase: f32,
+}
+
+fn start_celebration(
+    mut commands: Commands,
+    mut next_state: ResMut<NextState<GameState>>,
+    settings: Res<Settings>,
+    font_assets: Res<FontAssets>,
+    cube: Query<&Transform, With<CubeFrame>>,
+) {
+    let seconds = settings.gameplay.celebration_length;
+
+    // Everything here is motion, so there's nothing left to show without it.
+    // Settings are sanitized when loaded, but NaN would still make the timer
+    // panic, so check for it here too
+    if !seconds.is_finite() || seconds <= 0.0 || settings.accessibility.reduced_motion {
+        next_state.set(GameState::Reset);
+        return;
+    }
+    let seconds = seconds.min(*GameplaySettings::CELEBRATION_LENGTH.end());
+
+    commands.insert_resource(Celebration {
+        timer: Timer::from_seconds(seconds, TimerMode::Once),
+        start_rotation: cube
+            .get_single()
+            .map_or(Quat::IDENTITY, |transform| transform.rotation),
+    });
+
+    let mut rng = rand::thread_rng();
+
+    commands
+        .spawn((
+            CelebrationRoot,
+            IGNORE_PICKING,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                IGNORE_PICKING,
+                LocalizedText::new("celebration.title").bundle(font_assets.button_style()),
+            ));
+            parent.spawn((
+                IGNORE_PICKING,
+                LocalizedText::new("celebration.skip").bundle(TextStyle {
+                    font_size: 24.0,
+                    ..font_assets.button_style()
+                }),
+            ));
+
+            for _ in 0..CONFETTI_COUNT {
+                let confetti = Confetti {
+                    // Start above the top of the screen, bursting out sideways
+                    position: Vec2::new(rng.gen_range(0.0..100.0), rng.gen_range(-30.0..-5.0)),
+                    velocity: Vec2::new(rng.gen_range(-15.0..15.0), rng.gen_range(0.0..20.0)),
+                    phase: rng.gen_range(0.0..TAU),
+                };
+                let color = CONFETTI_COLORS[rng.gen_range(0..CONFETTI_COLORS.len())];
+
+                parent.spawn((
+                    IGNORE_PICKING,
+                    NodeBundle {
+                        style: Style {
+                            position_type: PositionType::Absolute,
+                            left: Val::Percent(confetti.position.x),
+                            top: Val::Percent(confetti.position.y),
+                            width: Val::Px(8.0),
+                            height: Val::Px(14.0),
+                            ..default()
+                        },
+                        background_color: color.into(),
+                        ..default()
+                    },
+                    confetti,
+                ));
+            }
+        });
+}
+
+fn spin_cube(celebration: Res<Celebration>, mut cube: Query<&mut Transform, With<CubeFrame>>) {
+    // Ease in and out, so the cube ends up facing the way it started
+    let t = celebration.progress();
+    let eased = t * t * (3.0 - 2.0 * t);
+
+    for mut transform in &mut cube {
+        transform.rotation = celebration.start_rotation * Quat::from_rotation_y(TAU * eased);
+    }
+}
+
+/// Push each block out and back, starting from the middle of the cube and
+/// spreading to the corners.
+fn wave_blocks(
+    celebration: Res<Celebration>,
+    animation: Res<PushAnimation>,
+    cube: Query<&GlobalTransform, With<CubeFrame>>,
+    resting: Query<&GlobalTransform, Without<Block>>,
+    mut blocks: Query<(&mut Transform, &Parent, &BlockTween), With<Block>>,
+) {
+    let Ok(center) = cube.get_single().map(GlobalTransform::translation) else {
+        return;
+    };
+
+    // Each block sits under a parent at its resting place, which the wave itself
+    // doesn't move, so the timing can't feed back into itself
+    let distance = |parent: &Parent| {
+        resting
+            .get(parent.get())
+            .map_or(0.0, |resting| resting.translation().distance(center))
+    };
+
+    let furthest = blocks
+        .iter()
+        .map(|(_, parent, _)| distance(parent))
+        .fold(0.0, f32::max)
+        .max(f32::EPSILON);
+
+    let t = celebration.progress();
+
+    for (mut transform, parent, tween) in &mut blocks {
+        let delay = distance(parent) / furthest * WAVE_SPREAD;
+        let pulse = ((t - delay) / (1.0 - WAVE_SPREAD)).clamp(0.0, 1.0);
+
+        // Blocks face outward, so "forward" is out of the cube
+        transform.translation =
+            tween.translation(&animation) + (PI * pulse).sin() * WAVE_DISTANCE * Vec3::NEG_Z;
+    }
+}
+
+fn fall_confetti(time: Res<Time>, mut confetti: Query<(&mut Confetti, &mut Style)>) {
+    let dt = time.delta_seconds();
+    let elapsed = time.elapsed_seconds();
+
+    for (mut piece, mut style) in &mut confetti {
+        piece.velocity.y += CONFETTI_GRAVITY * dt;
+        let velocity = piece.velocity;
+        piece.position += velocity * dt;
+
+        let flutter = (elapsed * 4.0 + piece.phase).sin() * 2.0;
+        style.left = Val::Percent(piece.position.x + flutter);
+        style.top = Val::Percent(piece.position.y);
+    }
+}
+
+fn finish_celebration(
+    time: Res<Time>,
+    mut celebration: ResMut<Celebration>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mouse: Res<Input<MouseButton>>,
+    keyboard: Res<Input<KeyCode>>,
+    touches: Res<Touches>,
+) {
+    let skipped = mouse.get_just_pressed().next().is_some()
+        || keyboard.get_just_pressed().next().is_some()
+        || touches.any_just_pressed();
+
+    if celebration.timer.tick(time.delta()).finished() || skipped {
+        next_state.set(GameState::Reset);
+    }
+}
+
+/// Put everything back how it was, in case the same blocks get reused.
+fn end_celebration(
+    mut commands: Commands,
+    celebration: Option<Res<Celebration>>,
+    animation: Res<PushAnimation>,
+    root: Query<Entity, With<CelebrationRoot>>,
+    mut cube: Query<&mut Transform, With<CubeFrame>>,
+    mut blocks: Query<(&mut Transform, &BlockTween), (With<Block>, Without<CubeFrame>)>,
+) {
+    for entity in &root {
+        commands.entity(entity).despawn_recursive();
+    }
+
+    if let Some(celebration) = celebration {
+        for mut transform in &mut cube {
+            transform.rotation = celebration.start_rotation;
+        }
+        commands.remove_resource::<Celebration>();
+    }
+
+    for (mut transform, tween) in &mut blocks {
+        transform.translation = tween.translation(&animation);
+    }
+}
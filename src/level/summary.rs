@@ -0,0 +1,255 @@
+//! The results panel shown once a level has been completed.
+
+use bevy::prelude::*;
+
+#[cfg(not(target_family = "wasm"))]
+use bevy::app::AppExit;
+
+use crate::cubes::{BlockCount, NewLevel, RestartLevel};
+use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::menu::{spawn_button, ButtonColors};
+use crate::progress::format_time;
+use crate::GameState;
+
+use super::LevelResult;
+
+pub struct SummaryPlugin;
+
+impl Plugin for SummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Reset), setup_summary)
+            .add_systems(
+                Update,
+                (click_continue, click_retry, click_level_select)
+                    .run_if(in_state(GameState::Reset)),
+            )
+            .add_systems(OnExit(GameState::Reset), cleanup_summary);
+
+        // "quitting" kinda just crashes the app in wasm, let's not do that
+        #[cfg(not(target_family = "wasm"))]
+        app.add_systems(Update, click_quit.run_if(in_state(GameState::Reset)));
+    }
+}
+
+#[derive(Component)]
+struct SummaryRoot;
+
+#[derive(Component)]
+struct ContinueButton;
+
+#[derive(Component)]
+struct RetryButton;
+
+#[derive(Component)]
+struct LevelSelectButton;
+
+#[cfg(not(target_family = "wasm"))]
+#[derive(Component)]
+struct QuitButton;
+
+fn par_text(moves: u32, par: u32) -> LocalizedText {
+    match moves.cmp(&par) {
+        std::cmp::Ordering::Greater => LocalizedText::with_args("summary.over_par", [moves - par]),
+        // Shouldn't really be possible, but just in case par is ever miscounted
+        std::cmp::Ordering::Less => LocalizedText::with_args("summary.under_par", [par - moves]),
+        std::cmp::Ordering::Equal => LocalizedText::new("summary.at_par"),
+    }
+}
+
+pub(super) fn setup_summary(
+    mut commands: Commands,
+    result: Res<LevelResult>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = TextStyle {
+        font_size: 30.0,
+        ..font_assets.button_style()
+    };
+
+    let best_marker = |is_best: bool| is_best.then(|| LocalizedText::new("summary.new_best"));
+
+    // Each line is made up of a few separately translated pieces
+    let lines = [
+        [
+            Some(LocalizedText::with_args(
+                "summary.time",
+                [format_time(result.elapsed)],
+            )),
+            None,
+            best_marker(result.new_bests.time),
+        ],
+        [
+            Some(LocalizedText::with_args("summary.moves", [result.moves])),
+            Some(par_text(result.moves, result.par)),
+            best_marker(result.new_bests.moves),
+        ],
+        [
+            Some(LocalizedText::with_args(
+                "summary.knocked_out",
+                [result.knocked_out],
+            )),
+            None,
+            None,
+        ],
+        [
+            Some(LocalizedText::with_args("summary.hints", [result.hints])),
+            None,
+            None,
+        ],
+        [
+            result
+                .code
+                .map(|code| LocalizedText::with_args("summary.code", [code])),
+            None,
+            None,
+        ],
+    ];
+
+    commands
+        .spawn((
+            SummaryRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(
+                LocalizedText::with_args("summary.title", [result.level])
+                    .bundle(font_assets.button_style()),
+            );
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Column,
+                        align_items: AlignItems::Center,
+                        margin: UiRect::vertical(Val::Px(16.0)),
+                        ..default()
+                    },
+                    ..default()
+                })
+                .with_children(|parent| {
+                    for line in lines {
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    column_gap: Val::Px(10.0),
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                for piece in line.into_iter().flatten() {
+                                    parent.spawn(piece.bundle(text_style.clone()));
+                                }
+                            });
+                    }
+                });
+
+            if result.next_level().is_some() {
+                spawn_button(
+                    parent,
+                    ContinueButton,
+                    "summary.continue",
+                    &font_assets,
+                    &button_colors,
+                );
+            }
+            spawn_button(
+                parent,
+                RetryButton,
+                "summary.retry",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                LevelSelectButton,
+                "menu.level_select",
+                &font_assets,
+                &button_colors,
+            );
+
+            #[cfg(not(target_family = "wasm"))]
+            spawn_button(
+                parent,
+                QuitButton,
+                "summary.quit",
+                &font_assets,
+                &button_colors,
+            );
+        });
+}
+
+fn click_continue(
+    mut state: ResMut<NextState<GameState>>,
+    mut block_count: ResMut<BlockCount>,
+    mut new_level: EventWriter<NewLevel>,
+    result: Res<LevelResult>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<ContinueButton>)>,
+) {
+    let Some(next_level) = result.next_level() else {
+        return;
+    };
+
+    for interaction in &interaction {
+        if let Interaction::Pressed = *interaction {
+            block_count.0 = next_level;
+            new_level.send(NewLevel);
+            state.set(GameState::Playing);
+        }
+    }
+}
+
+fn click_retry(
+    mut state: ResMut<NextState<GameState>>,
+    mut restart: EventWriter<RestartLevel>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<RetryButton>)>,
+) {
+    for interaction in &interaction {
+        if let Interaction::Pressed = *interaction {
+            restart.send(RestartLevel);
+            state.set(GameState::Playing);
+        }
+    }
+}
+
+fn click_level_select(
+    mut state: ResMut<NextState<GameState>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<LevelSelectButton>)>,
+) {
+    for interaction in &interaction {
+        if let Interaction::Pressed = *interaction {
+            state.set(GameState::LevelSelect);
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn click_quit(
+    mut quit_interaction: Query<&Interaction, (Changed<Interaction>, With<QuitButton>)>,
+    mut quit: EventWriter<AppExit>,
+) {
+    for interaction in &mut quit_interaction {
+        if let Interaction::Pressed = *interaction {
+            quit.send(AppExit);
+        }
+    }
+}
+
+fn cleanup_summary(mut commands: Commands, root: Query<Entity, With<SummaryRoot>>) {
+    for entity in &root {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,197 @@
+use bevy::prelude::*;
+use bevy_mod_picking::picking_core::Pickable;
+
+use crate::cubes::{BlockCount, NewLevel};
+use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::menu::{spawn_button, ButtonColors};
+use crate::progress::{format_time, Progress, LEVEL_COUNT};
+use crate::GameState;
+
+pub struct LevelSelectPlugin;
+
+/// This plugin lists every level along with the player's records for it, and
+/// lets them jump straight into any level they've unlocked.
+impl Plugin for LevelSelectPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::LevelSelect), setup_level_select)
+            .add_systems(
+                Update,
+                (click_level_button, click_back_button).run_if(in_state(GameState::LevelSelect)),
+            )
+            .add_systems(OnExit(GameState::LevelSelect), cleanup_level_select);
+    }
+}
+
+#[derive(Component)]
+struct LevelSelectRoot;
+
+/// Starts the level with this many blocks per axis.
+#[derive(Component)]
+struct LevelButton(i16);
+
+#[derive(Component)]
+struct BackButton;
+
+fn setup_level_select(
+    mut commands: Commands,
+    progress: Res<Progress>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = TextStyle {
+        font_size: 26.0,
+        ..font_assets.button_style()
+    };
+
+    // Each column gets a fixed width so the rows line up like a table
+    let cell = |width: f32| NodeBundle {
+        style: Style {
+            width: Val::Px(width),
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        ..default()
+    };
+
+    commands
+        .spawn((
+            LevelSelectRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn(LocalizedText::new("level_select.title").bundle(font_assets.button_style()));
+
+            for level in 1..=LEVEL_COUNT {
+                let record = progress.levels.get(&level);
+                let unlocked = progress.is_unlocked(level);
+
+                let status = if progress.is_completed(level) {
+                    "level_select.done"
+                } else if unlocked {
+                    "level_select.unlocked"
+                } else {
+                    "level_select.locked"
+                };
+                let best_time = record.and_then(|record| record.best_time).map_or_else(
+                    || LocalizedText::new("level_select.no_record"),
+                    |time| LocalizedText::with_args("level_select.time", [format_time(time)]),
+                );
+                let best_moves = record.and_then(|record| record.best_moves).map_or_else(
+                    || LocalizedText::new("level_select.no_record"),
+                    |moves| LocalizedText::with_args("level_select.moves", [moves]),
+                );
+
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            height: Val::Px(36.0),
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        let columns = [
+                            (
+                                LocalizedText::with_args("level_select.size", [level; 3]),
+                                140.0,
+                            ),
+                            (LocalizedText::new(status), 110.0),
+                            (best_time, 90.0),
+                            (best_moves, 140.0),
+                        ];
+
+                        for (text, width) in columns {
+                            parent.spawn(cell(width)).with_children(|parent| {
+                                parent.spawn(text.bundle(text_style.clone()));
+                            });
+                        }
+
+                        parent.spawn(cell(90.0)).with_children(|parent| {
+                            if !unlocked {
+                                return;
+                            }
+
+                            parent
+                                .spawn((
+                                    LevelButton(level),
+                                    // Don't let the click reach the freshly spawned cube
+                                    Pickable {
+                                        should_block_lower: true,
+                                        should_emit_events: false,
+                                    },
+                                    ButtonBundle {
+                                        style: Style {
+                                            width: Val::Px(80.0),
+                                            height: Val::Px(30.0),
+                                            justify_content: JustifyContent::Center,
+                                            align_items: AlignItems::Center,
+                                            ..default()
+                                        },
+                                        background_color: button_colors.normal.into(),
+                                        ..default()
+                                    },
+                                ))
+                                .with_children(|parent| {
+                                    parent.spawn(
+                                        LocalizedText::new("menu.play").bundle(text_style.clone()),
+                                    );
+                                });
+                        });
+                    });
+            }
+
+            spawn_button(
+                parent,
+                BackButton,
+                "common.back",
+                &font_assets,
+                &button_colors,
+            );
+        });
+}
+
+fn click_level_button(
+    mut state: ResMut<NextState<GameState>>,
+    mut block_count: ResMut<BlockCount>,
+    mut new_level: EventWriter<NewLevel>,
+    interaction: Query<(&Interaction, &LevelButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction {
+        if let Interaction::Pressed = *interaction {
+            block_count.0 = button.0;
+            new_level.send(NewLevel);
+            state.set(GameState::Playing);
+        }
+    }
+}
+
+fn click_back_button(
+    mut state: ResMut<NextState<GameState>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+) {
+    for interaction in &interaction {
+        if let Interaction::Pressed = *interaction {
+            state.set(GameState::Menu);
+        }
+    }
+}
+
+fn cleanup_level_select(mut commands: Commands, root: Query<Entity, With<LevelSelectRoot>>) {
+    for entity in &root {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -2,17 +2,34 @@
 
 mod actions;
 mod cubes;
+mod hud;
 mod level;
+mod level_select;
 mod loading;
+mod localization;
 mod macros;
 mod menu;
+mod pause;
 mod player;
+mod progress;
+mod settings;
+mod storage;
+mod theme;
+mod tutorial;
 
 use self::actions::ActionsPlugin;
 use self::cubes::CubePlugin;
+use self::hud::HudPlugin;
+use self::level_select::LevelSelectPlugin;
 use self::loading::LoadingPlugin;
+use self::localization::LocalizationPlugin;
 use self::menu::MenuPlugin;
+use self::pause::PausePlugin;
 use self::player::PlayerPlugin;
+use self::progress::ProgressPlugin;
+use self::settings::SettingsPlugin;
+use self::theme::ThemePlugin;
+use self::tutorial::TutorialPlugin;
 
 use bevy::app::App;
 use bevy::prelude::*;
@@ -21,6 +38,9 @@ use bevy::prelude::*;
 use bevy::diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin};
 use level::LevelPlugin;
 
+#[doc(hidden)]
+pub use self::cubes::bench;
+
 // This example game uses States to separate logic
 // See https://bevy-cheatbook.github.io/programming/states.html
 // Or https://github.com/bevyengine/bevy/blob/main/examples/ecs/state.rs
@@ -31,8 +51,14 @@ enum GameState {
     Loading,
     /// Here the menu is drawn and waiting for player interaction
     Menu,
+    /// The player is picking which level to play.
+    LevelSelect,
     /// During this State the actual game logic is executed
     Playing,
+    /// The game is frozen and the pause menu is open.
+    Paused,
+    /// The level was just solved, and the cube is showing off before the results.
+    Celebrating,
     /// The level is complete and being prepared for the next level.
     Reset,
 }
@@ -42,8 +68,16 @@ pub struct GamePlugin;
 impl Plugin for GamePlugin {
     fn build(&self, app: &mut App) {
         app.add_state::<GameState>().add_plugins((
+            SettingsPlugin,
+            ProgressPlugin,
+            LocalizationPlugin,
+            ThemePlugin,
             LoadingPlugin,
             MenuPlugin,
+            LevelSelectPlugin,
+            PausePlugin,
+            HudPlugin,
+            TutorialPlugin,
             LevelPlugin,
             ActionsPlugin,
             CubePlugin,
@@ -53,14 +87,10 @@ impl Plugin for GamePlugin {
         #[cfg(debug_assertions)]
         {
             app.add_plugins((FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin::default()))
-                // TODO: pause menu with better quitting...
+                // Only while playing, so typing an N somewhere else doesn't skip ahead
                 .add_systems(
                     Update,
-                    (
-                        #[cfg(not(target_family = "wasm"))]
-                        bevy::window::close_on_esc,
-                        level::skip_level,
-                    ),
+                    level::skip_level.run_if(in_state(GameState::Playing)),
                 );
         }
     }
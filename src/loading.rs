@@ -2,6 +2,8 @@ use bevy::gltf::Gltf;
 use bevy::prelude::*;
 use bevy_asset_loader::prelude::*;
 
+use crate::localization::LanguageAssets;
+use crate::theme::ThemeAssets;
 use crate::GameState;
 
 pub struct LoadingPlugin;
@@ -17,7 +19,9 @@ impl Plugin for LoadingPlugin {
         .configure_loading_state(
             LoadingStateConfig::new(GameState::Loading)
                 .load_collection::<FontAssets>()
-                .load_collection::<GLTFAssets>(),
+                .load_collection::<GLTFAssets>()
+                .load_collection::<LanguageAssets>()
+                .load_collection::<ThemeAssets>(),
         );
     }
 }
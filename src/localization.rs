@@ -0,0 +1,255 @@
+//! Translated UI text. Every language has a `assets/lang/<code>.lang.ron` file
+//! mapping string keys to text, and any UI text that should follow the language
+//! setting is spawned with a [`LocalizedText`] instead of a hardcoded string.
+
+use std::collections::HashMap;
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::prelude::*;
+use bevy::ui::UiSystem;
+use bevy::utils::BoxedFuture;
+use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::loading::FontAssets;
+use crate::settings::Settings;
+use crate::GameState;
+
+pub struct LocalizationPlugin;
+
+/// This plugin loads the string files for every language, and keeps all
+/// [`LocalizedText`] up to date with the language setting.
+impl Plugin for LocalizationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<Translations>()
+            .register_asset_loader(TranslationsLoader)
+            .init_resource::<Localization>()
+            .add_systems(OnExit(GameState::Loading), update_localization)
+            .add_systems(
+                Update,
+                update_localization
+                    .run_if(resource_exists::<LanguageAssets>())
+                    .run_if(
+                        resource_changed::<Settings>()
+                            .or_else(on_event::<AssetEvent<Translations>>()),
+                    ),
+            )
+            // Fill in text spawned this frame before it gets laid out for the first time
+            .add_systems(
+                PostUpdate,
+                (localize_texts, apply_font).before(UiSystem::Layout),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Language {
+    #[default]
+    English,
+    Spanish,
+    German,
+    French,
+}
+
+impl Language {
+    pub const ALL: [Language; 4] = [
+        Language::English,
+        Language::Spanish,
+        Language::German,
+        Language::French,
+    ];
+
+    /// The name of the language, written in that language.
+    pub fn native_name(self) -> &'static str {
+        match self {
+            Self::English => "English",
+            Self::Spanish => "Español",
+            Self::German => "Deutsch",
+            Self::French => "Français",
+        }
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct LanguageAssets {
+    #[asset(path = "lang/en.lang.ron")]
+    pub english: Handle<Translations>,
+    #[asset(path = "lang/es.lang.ron")]
+    pub spanish: Handle<Translations>,
+    #[asset(path = "lang/de.lang.ron")]
+    pub german: Handle<Translations>,
+    #[asset(path = "lang/fr.lang.ron")]
+    pub french: Handle<Translations>,
+}
+
+impl LanguageAssets {
+    fn get(&self, language: Language) -> &Handle<Translations> {
+        match language {
+            Language::English => &self.english,
+            Language::Spanish => &self.spanish,
+            Language::German => &self.german,
+            Language::French => &self.french,
+        }
+    }
+}
+
+/// The contents of a single language's string file.
+#[derive(Asset, TypePath, Deserialize, Debug)]
+pub struct Translations {
+    /// A font to use instead of the default one for all text while this language
+    /// is picked, for scripts the default one can't render.
+    #[serde(default)]
+    font: Option<String>,
+    strings: HashMap<String, String>,
+}
+
+#[derive(Default)]
+struct TranslationsLoader;
+
+impl AssetLoader for TranslationsLoader {
+    type Asset = Translations;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        _load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            Ok(ron::de::from_bytes(&bytes)?)
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["lang.ron"]
+    }
+}
+
+/// The strings for the current language, falling back to English for anything
+/// that hasn't been translated yet.
+#[derive(Resource, Default)]
+pub struct Localization {
+    strings: HashMap<String, String>,
+    fallback: HashMap<String, String>,
+    font: Option<Handle<Font>>,
+}
+
+impl Localization {
+    pub fn get<'a>(&'a self, key: &'a str) -> &'a str {
+        self.strings
+            .get(key)
+            .or_else(|| self.fallback.get(key))
+            .map_or(key, String::as_str)
+    }
+
+    /// Look up a string and fill in each `{}` with the next argument.
+    pub fn format(&self, key: &str, args: &[String]) -> String {
+        let mut args = args.iter();
+        let mut pieces = self.get(key).split("{}");
+
+        let mut formatted = pieces.next().unwrap_or_default().to_string();
+        for piece in pieces {
+            formatted.push_str(args.next().map_or("", String::as_str));
+            formatted.push_str(piece);
+        }
+
+        formatted
+    }
+}
+
+/// Text that gets looked up in the current language's strings. Only the first
+/// section of the entity's [`Text`] is replaced.
+#[derive(Component, Clone, PartialEq, Debug)]
+pub struct LocalizedText {
+    pub key: &'static str,
+    pub args: Vec<String>,
+}
+
+impl LocalizedText {
+    pub fn new(key: &'static str) -> Self {
+        Self {
+            key,
+            args: Vec::new(),
+        }
+    }
+
+    pub fn with_args<T: ToString>(key: &'static str, args: impl IntoIterator<Item = T>) -> Self {
+        Self {
+            key,
+            args: args.into_iter().map(|arg| arg.to_string()).collect(),
+        }
+    }
+
+    /// A text bundle that gets filled in with this text before it's first drawn.
+    pub fn bundle(self, style: TextStyle) -> (Self, TextBundle) {
+        (self, TextBundle::from_section("", style))
+    }
+}
+
+fn update_localization(
+    mut localization: ResMut<Localization>,
+    settings: Res<Settings>,
+    languages: Res<LanguageAssets>,
+    translations: Res<Assets<Translations>>,
+    font_assets: Res<FontAssets>,
+    asset_server: Res<AssetServer>,
+) {
+    let strings_for = |language| {
+        translations
+            .get(languages.get(language))
+            .map(|translations| translations.strings.clone())
+            .unwrap_or_default()
+    };
+
+    let current = translations.get(languages.get(settings.language));
+
+    localization.strings = strings_for(settings.language);
+    localization.fallback = strings_for(Language::English);
+    localization.font = Some(
+        current
+            .and_then(|translations| translations.font.clone())
+            .map_or_else(
+                || font_assets.suwannaphum.clone(),
+                |path| asset_server.load(path),
+            ),
+    );
+}
+
+fn localize_texts(
+    localization: Res<Localization>,
+    mut texts: Query<(Ref<LocalizedText>, &mut Text)>,
+) {
+    for (localized, mut text) in &mut texts {
+        if !localization.is_changed() && !localized.is_changed() {
+            continue;
+        }
+
+        text.sections[0].value = localization.format(localized.key, &localized.args);
+    }
+}
+
+/// Draw every text with the current language's font, including ones that aren't
+/// localized, like arrows and typed in puzzle codes.
+fn apply_font(localization: Res<Localization>, mut texts: Query<&mut Text>) {
+    let Some(font) = &localization.font else {
+        return;
+    };
+
+    for mut text in &mut texts {
+        // Only touch texts that need it, so they aren't marked as changed every frame
+        if text
+            .sections
+            .iter()
+            .any(|section| section.style.font != *font)
+        {
+            for section in &mut text.sections {
+                section.style.font = font.clone();
+            }
+        }
+    }
+}
@@ -24,10 +24,8 @@ fn main() {
     };
 
     App::new()
-        .insert_resource(Msaa::Sample4)
         // https://github.com/bevyengine/bevy/issues/10157
         .insert_resource(AssetMetaCheck::Never)
-        .insert_resource(ClearColor(Color::rgb(0.91, 0.76, 0.45)))
         .add_plugins((
             DefaultPlugins
                 .set(WindowPlugin {
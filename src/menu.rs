@@ -1,27 +1,47 @@
 use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::settings::{Settings, SettingsButton};
 use crate::GameState;
 use bevy::prelude::*;
 use bevy_mod_picking::picking_core::Pickable;
 
+use self::code_entry::{CodeEntryPlugin, EnterCodeButton};
+
+mod code_entry;
+
 pub struct MenuPlugin;
 
-/// This plugin is responsible for the game menu (containing only one button...)
-/// The menu is only drawn during the State `GameState::Menu` and is removed when that state is exited
+/// This plugin is responsible for the game menu, and the button styling shared by the rest
+/// of the UI. The menu is only drawn during the State `GameState::Menu` and is removed when
+/// that state is exited
 impl Plugin for MenuPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<ButtonColors>()
+            .add_plugins(CodeEntryPlugin)
+            .add_systems(OnExit(GameState::Loading), spawn_camera)
             .add_systems(OnEnter(GameState::Menu), setup_menu)
             .add_systems(
                 Update,
                 (
-                    color_buttons,
-                    click_play_button.run_if(in_state(GameState::Menu)),
+                    (
+                        update_button_colors.run_if(resource_changed::<Settings>()),
+                        color_buttons,
+                    )
+                        .chain(),
+                    (click_play_button, click_level_select_button)
+                        .run_if(in_state(GameState::Menu)),
                 ),
             )
             .add_systems(OnExit(GameState::Menu), cleanup_menu);
     }
 }
 
+/// For UI that's purely informational, so clicks go straight through to the cube.
+pub const IGNORE_PICKING: Pickable = Pickable {
+    should_block_lower: false,
+    should_emit_events: false,
+};
+
 #[derive(Resource)]
 pub struct ButtonColors {
     pub normal: Color,
@@ -31,6 +51,12 @@ pub struct ButtonColors {
 #[derive(Component)]
 pub struct PlayButton;
 
+#[derive(Component)]
+struct LevelSelectButton;
+
+#[derive(Component)]
+struct MenuRoot;
+
 impl Default for ButtonColors {
     fn default() -> Self {
         ButtonColors {
@@ -40,13 +66,23 @@ impl Default for ButtonColors {
     }
 }
 
-fn setup_menu(
-    mut commands: Commands,
-    font_assets: Res<FontAssets>,
-    button_colors: Res<ButtonColors>,
-) {
+impl ButtonColors {
+    fn high_contrast() -> Self {
+        ButtonColors {
+            normal: Color::BLACK,
+            hovered: Color::rgb_u8(0, 50, 130),
+        }
+    }
+}
+
+/// Where the camera starts out, looking at the cube from slightly above and to the left.
+pub fn menu_camera_transform() -> Transform {
+    Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y)
+}
+
+fn spawn_camera(mut commands: Commands) {
     commands.spawn(Camera3dBundle {
-        transform: Transform::from_xyz(-2.0, 2.5, 5.0).looking_at(Vec3::ZERO, Vec3::Y),
+        transform: menu_camera_transform(),
         camera: Camera {
             order: -10,
             is_active: true,
@@ -54,42 +90,121 @@ fn setup_menu(
         },
         ..default()
     });
+}
+
+fn setup_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+    mut camera: Query<&mut Transform, With<Camera3d>>,
+) {
+    // We might be coming back from a game, so put the camera back where it started
+    for mut transform in &mut camera {
+        *transform = menu_camera_transform();
+    }
 
     commands
         .spawn((
-            PlayButton,
-            // Prevent clicks from passing through the button after clicking it
-            Pickable {
-                should_block_lower: true,
-                should_emit_events: false,
+            MenuRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                ..default()
             },
+        ))
+        .with_children(|parent| {
+            spawn_button(
+                parent,
+                (
+                    PlayButton,
+                    // Prevent clicks from passing through the button after clicking it
+                    Pickable {
+                        should_block_lower: true,
+                        should_emit_events: false,
+                    },
+                ),
+                "menu.play",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                LevelSelectButton,
+                "menu.level_select",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                EnterCodeButton,
+                "menu.enter_code",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                SettingsButton,
+                "menu.settings",
+                &font_assets,
+                &button_colors,
+            );
+        });
+}
+
+/// Spawn a button with a text label, styled like the rest of the UI. The label is a
+/// key into the current language's strings.
+pub fn spawn_button(
+    parent: &mut ChildBuilder,
+    marker: impl Bundle,
+    label: &'static str,
+    font_assets: &FontAssets,
+    button_colors: &ButtonColors,
+) {
+    parent
+        .spawn((
+            marker,
             ButtonBundle {
                 style: Style {
-                    width: Val::Px(120.0),
-                    height: Val::Px(50.0),
-                    margin: UiRect::all(Val::Auto),
+                    width: Val::Px(300.0),
+                    height: Val::Px(60.0),
+                    margin: UiRect::all(Val::Px(8.0)),
                     justify_content: JustifyContent::Center,
                     align_items: AlignItems::Center,
-                    ..Default::default()
+                    ..default()
                 },
                 background_color: button_colors.normal.into(),
-                ..Default::default()
+                ..default()
             },
         ))
         .with_children(|parent| {
-            parent.spawn(TextBundle::from_section("Play", font_assets.button_style()));
+            parent.spawn(LocalizedText::new(label).bundle(font_assets.button_style()));
         });
 }
 
-#[allow(clippy::type_complexity)]
+fn update_button_colors(settings: Res<Settings>, mut button_colors: ResMut<ButtonColors>) {
+    *button_colors = if settings.accessibility.high_contrast {
+        ButtonColors::high_contrast()
+    } else {
+        ButtonColors::default()
+    };
+}
+
 fn color_buttons(
     button_colors: Res<ButtonColors>,
-    mut interaction_query: Query<
-        (&Interaction, &mut BackgroundColor),
-        (Changed<Interaction>, With<Button>),
-    >,
+    mut interaction_query: Query<(Ref<Interaction>, &mut BackgroundColor), With<Button>>,
 ) {
     for (interaction, mut color) in &mut interaction_query {
+        // Recolor everything if the colors themselves changed
+        if !interaction.is_changed() && !button_colors.is_changed() {
+            continue;
+        }
+
         match *interaction {
             Interaction::Hovered => {
                 *color = button_colors.hovered.into();
@@ -113,8 +228,19 @@ fn click_play_button(
     }
 }
 
-fn cleanup_menu(mut commands: Commands, buttons: Query<Entity, With<Button>>) {
-    for button in &buttons {
-        commands.entity(button).despawn_recursive();
+fn click_level_select_button(
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction_query: Query<&Interaction, (Changed<Interaction>, With<LevelSelectButton>)>,
+) {
+    for interaction in &mut interaction_query {
+        if let Interaction::Pressed = *interaction {
+            state.set(GameState::LevelSelect);
+        }
+    }
+}
+
+fn cleanup_menu(mut commands: Commands, menu: Query<Entity, With<MenuRoot>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
     }
 }
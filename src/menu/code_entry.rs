@@ -0,0 +1,262 @@
+//! A screen on top of the main menu for typing in a puzzle code that someone
+//! shared, and playing that exact puzzle.
+
+use bevy::log;
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+use bevy::window::ReceivedCharacter;
+use bevy_mod_picking::picking_core::Pickable;
+
+use crate::cubes::{
+    BlockCount, LoadPuzzle, PuzzleCode, PuzzleCodeError, GENERATOR_VERSION, MAX_BLOCK_COUNT,
+};
+use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::GameState;
+
+use super::{spawn_button, ButtonColors};
+
+pub struct CodeEntryPlugin;
+
+impl Plugin for CodeEntryPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<CodeEntryScreen>()
+            .add_systems(OnEnter(CodeEntryScreen::Open), setup_code_entry)
+            .add_systems(OnExit(CodeEntryScreen::Open), cleanup_code_entry)
+            .add_systems(
+                Update,
+                (
+                    click_enter_code_button.run_if(in_state(CodeEntryScreen::Closed)),
+                    (type_code, submit_code, close_code_entry)
+                        .chain()
+                        .run_if(in_state(CodeEntryScreen::Open)),
+                ),
+            );
+    }
+}
+
+/// Whether the code entry screen is showing over the main menu.
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+enum CodeEntryScreen {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// Longer than any code the game makes, with room for stray spaces.
+const MAX_CODE_LENGTH: usize = 32;
+
+/// Opens the code entry screen when clicked.
+#[derive(Component)]
+pub struct EnterCodeButton;
+
+#[derive(Component)]
+struct CodeEntryRoot;
+
+/// The text the player has typed so far.
+#[derive(Component, Default)]
+struct CodeField(String);
+
+/// Explains what to type, or what was wrong with the last code.
+#[derive(Component)]
+struct CodeMessage;
+
+#[derive(Component)]
+struct PlayCodeButton;
+
+#[derive(Component)]
+struct BackButton;
+
+fn click_enter_code_button(
+    mut screen: ResMut<NextState<CodeEntryScreen>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<EnterCodeButton>)>,
+) {
+    for interaction in &interaction {
+        if let Interaction::Pressed = *interaction {
+            screen.set(CodeEntryScreen::Open);
+        }
+    }
+}
+
+fn setup_code_entry(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = TextStyle {
+        font_size: 26.0,
+        ..font_assets.button_style()
+    };
+
+    commands
+        .spawn((
+            CodeEntryRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                // Draw over, and keep clicks away from, the menu underneath
+                focus_policy: FocusPolicy::Block,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(LocalizedText::new("menu.enter_code").bundle(font_assets.button_style()));
+
+            parent
+                .spawn(NodeBundle {
+                    style: Style {
+                        width: Val::Px(400.0),
+                        height: Val::Px(50.0),
+                        margin: UiRect::vertical(Val::Px(16.0)),
+                        border: UiRect::all(Val::Px(2.0)),
+                        justify_content: JustifyContent::Center,
+                        align_items: AlignItems::Center,
+                        ..default()
+                    },
+                    background_color: Color::rgba(1.0, 1.0, 1.0, 0.1).into(),
+                    border_color: button_colors.normal.into(),
+                    ..default()
+                })
+                .with_children(|parent| {
+                    parent.spawn((
+                        CodeField::default(),
+                        TextBundle::from_section("", font_assets.button_style()),
+                    ));
+                });
+
+            parent.spawn((
+                CodeMessage,
+                LocalizedText::new("code_entry.hint").bundle(text_style.clone()),
+            ));
+
+            spawn_button(
+                parent,
+                (
+                    PlayCodeButton,
+                    // Don't let the click reach the freshly spawned cube
+                    Pickable {
+                        should_block_lower: true,
+                        should_emit_events: false,
+                    },
+                ),
+                "menu.play",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                BackButton,
+                "common.back",
+                &font_assets,
+                &button_colors,
+            );
+        });
+}
+
+fn type_code(
+    mut characters: EventReader<ReceivedCharacter>,
+    keyboard_input: Res<Input<KeyCode>>,
+    mut field: Query<(&mut CodeField, &mut Text)>,
+) {
+    let Ok((mut field, mut text)) = field.get_single_mut() else {
+        return;
+    };
+
+    // Anything that can't be part of a code is dropped right away, which also
+    // keeps out control characters like backspace
+    for event in characters.read() {
+        let c = event.char;
+        let allowed = c.is_ascii_alphanumeric() || "-*~$=".contains(c);
+        if allowed && field.0.len() < MAX_CODE_LENGTH {
+            field.0.push(c.to_ascii_uppercase());
+        }
+    }
+
+    if keyboard_input.just_pressed(KeyCode::Back) {
+        field.0.pop();
+    }
+
+    if field.is_changed() {
+        text.sections[0].value = field.0.clone();
+    }
+}
+
+fn submit_code(
+    mut state: ResMut<NextState<GameState>>,
+    mut screen: ResMut<NextState<CodeEntryScreen>>,
+    mut block_count: ResMut<BlockCount>,
+    mut load: EventWriter<LoadPuzzle>,
+    keyboard_input: Res<Input<KeyCode>>,
+    field: Query<&CodeField>,
+    mut message: Query<&mut LocalizedText, With<CodeMessage>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<PlayCodeButton>)>,
+) {
+    let play_pressed = interaction
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    if !play_pressed && !keyboard_input.just_pressed(KeyCode::Return) {
+        return;
+    }
+
+    let Ok(field) = field.get_single() else {
+        return;
+    };
+
+    match field.0.parse::<PuzzleCode>() {
+        Ok(code) => {
+            block_count.0 = code.block_count;
+            load.send(LoadPuzzle(code));
+            screen.set(CodeEntryScreen::Closed);
+            state.set(GameState::Playing);
+        }
+        Err(error) => {
+            log::info!("rejected puzzle code {:?}: {error}", field.0);
+
+            for mut text in &mut message {
+                *text = error_text(error);
+            }
+        }
+    }
+}
+
+fn error_text(error: PuzzleCodeError) -> LocalizedText {
+    match error {
+        PuzzleCodeError::Malformed => LocalizedText::new("code_entry.malformed"),
+        PuzzleCodeError::Mistyped => LocalizedText::new("code_entry.mistyped"),
+        PuzzleCodeError::UnsupportedVersion(version) => {
+            LocalizedText::with_args("code_entry.version", [version, GENERATOR_VERSION])
+        }
+        PuzzleCodeError::InvalidSize(size) => {
+            LocalizedText::with_args("code_entry.size", [MAX_BLOCK_COUNT, size])
+        }
+    }
+}
+
+fn close_code_entry(
+    mut screen: ResMut<NextState<CodeEntryScreen>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+) {
+    let back_pressed = interaction
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    if back_pressed || keyboard_input.just_pressed(KeyCode::Escape) {
+        screen.set(CodeEntryScreen::Closed);
+    }
+}
+
+fn cleanup_code_entry(mut commands: Commands, root: Query<Entity, With<CodeEntryRoot>>) {
+    for entity in &root {
+        commands.entity(entity).despawn_recursive();
+    }
+}
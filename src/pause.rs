@@ -0,0 +1,204 @@
+use bevy::prelude::*;
+
+#[cfg(not(target_family = "wasm"))]
+use bevy::app::AppExit;
+
+use crate::cubes::RestartLevel;
+use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::menu::{spawn_button, ButtonColors};
+use crate::settings::{SettingsButton, SettingsScreen};
+use crate::GameState;
+
+pub struct PausePlugin;
+
+/// This plugin lets the player step away from a level in progress. While paused,
+/// virtual time stands still, which freezes block animations and toggle timers.
+impl Plugin for PausePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(OnEnter(GameState::Paused), (pause_time, setup_pause_menu))
+            .add_systems(
+                OnExit(GameState::Paused),
+                (unpause_time, cleanup_pause_menu),
+            )
+            .add_systems(
+                Update,
+                (
+                    // Escape closes the settings screen first, if it's open
+                    toggle_pause
+                        .run_if(in_state(GameState::Playing).or_else(in_state(GameState::Paused)))
+                        .run_if(in_state(SettingsScreen::Closed)),
+                    (click_resume, click_restart, click_main_menu)
+                        .run_if(in_state(GameState::Paused)),
+                ),
+            );
+
+        // "quitting" kinda just crashes the app in wasm, let's not do that
+        #[cfg(not(target_family = "wasm"))]
+        app.add_systems(Update, click_quit.run_if(in_state(GameState::Paused)));
+    }
+}
+
+#[derive(Component)]
+struct PauseMenu;
+
+#[derive(Component)]
+struct ResumeButton;
+
+#[derive(Component)]
+struct RestartButton;
+
+#[derive(Component)]
+struct MainMenuButton;
+
+#[cfg(not(target_family = "wasm"))]
+#[derive(Component)]
+struct QuitButton;
+
+fn toggle_pause(
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    gamepads: Res<Gamepads>,
+    gamepad_buttons: Res<Input<GamepadButton>>,
+) {
+    let start_pressed = gamepads.iter().any(|gamepad| {
+        gamepad_buttons.just_pressed(GamepadButton::new(gamepad, GamepadButtonType::Start))
+    });
+
+    if !keyboard_input.just_pressed(KeyCode::Escape) && !start_pressed {
+        return;
+    }
+
+    match state.get() {
+        GameState::Playing => next_state.set(GameState::Paused),
+        GameState::Paused => next_state.set(GameState::Playing),
+        _ => {}
+    }
+}
+
+fn pause_time(mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+}
+
+fn unpause_time(mut time: ResMut<Time<Virtual>>) {
+    time.unpause();
+}
+
+fn setup_pause_menu(
+    mut commands: Commands,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    commands
+        .spawn((
+            PauseMenu,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                // dim the cube a bit so it's obvious it can't be played with
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.4).into(),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(LocalizedText::new("pause.title").bundle(font_assets.button_style()));
+
+            spawn_button(
+                parent,
+                ResumeButton,
+                "pause.resume",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                RestartButton,
+                "pause.restart",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                SettingsButton,
+                "menu.settings",
+                &font_assets,
+                &button_colors,
+            );
+            spawn_button(
+                parent,
+                MainMenuButton,
+                "pause.quit_to_menu",
+                &font_assets,
+                &button_colors,
+            );
+
+            #[cfg(not(target_family = "wasm"))]
+            spawn_button(
+                parent,
+                QuitButton,
+                "pause.quit_to_desktop",
+                &font_assets,
+                &button_colors,
+            );
+        });
+}
+
+fn click_resume(
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction: Query<&Interaction, (Changed<Interaction>, With<ResumeButton>)>,
+) {
+    for interaction in &mut interaction {
+        if let Interaction::Pressed = *interaction {
+            state.set(GameState::Playing);
+        }
+    }
+}
+
+fn click_restart(
+    mut state: ResMut<NextState<GameState>>,
+    mut restart: EventWriter<RestartLevel>,
+    mut interaction: Query<&Interaction, (Changed<Interaction>, With<RestartButton>)>,
+) {
+    for interaction in &mut interaction {
+        if let Interaction::Pressed = *interaction {
+            restart.send(RestartLevel);
+            state.set(GameState::Playing);
+        }
+    }
+}
+
+fn click_main_menu(
+    mut state: ResMut<NextState<GameState>>,
+    mut interaction: Query<&Interaction, (Changed<Interaction>, With<MainMenuButton>)>,
+) {
+    for interaction in &mut interaction {
+        if let Interaction::Pressed = *interaction {
+            state.set(GameState::Menu);
+        }
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+fn click_quit(
+    mut interaction: Query<&Interaction, (Changed<Interaction>, With<QuitButton>)>,
+    mut quit: EventWriter<AppExit>,
+) {
+    for interaction in &mut interaction {
+        if let Interaction::Pressed = *interaction {
+            quit.send(AppExit);
+        }
+    }
+}
+
+fn cleanup_pause_menu(mut commands: Commands, menu: Query<Entity, With<PauseMenu>>) {
+    for entity in &menu {
+        commands.entity(entity).despawn_recursive();
+    }
+}
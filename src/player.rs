@@ -4,71 +4,132 @@ use bevy::prelude::*;
 
 use crate::actions::Actions;
 use crate::cubes::CubeFrame;
-use crate::tweak;
+use crate::settings::Settings;
+use crate::theme::CurrentTheme;
 use crate::GameState;
 
+mod framing;
+mod snap;
+
+use self::framing::{CameraFraming, MIN_CAMERA_DISTANCE};
+
 /// This plugin handles player related stuff like movement
 /// Player logic is only active during the State `GameState::Playing`
 pub struct PlayerPlugin;
 
 impl Plugin for PlayerPlugin {
     fn build(&self, app: &mut App) {
-        app.insert_resource(AmbientLight {
-            color: Color::BEIGE,
-            brightness: 0.1,
-        })
-        .add_systems(OnEnter(GameState::Playing), spawn_light)
-        .add_systems(Update, rotate_camera.run_if(in_state(GameState::Playing)));
+        app.init_resource::<CameraFraming>()
+            .add_systems(OnEnter(GameState::Playing), spawn_lights)
+            .add_systems(
+                Update,
+                spawn_lights.run_if(resource_changed::<CurrentTheme>()),
+            )
+            .add_systems(
+                Update,
+                (framing::apply_projection_mode, framing::frame_camera).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    rotate_camera,
+                    zoom_camera,
+                    snap::start_camera_snap,
+                    snap::animate_camera_snap,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            );
     }
 }
 
-fn spawn_light(mut commands: Commands, camera: Query<(Entity, &Transform), With<Camera3d>>) {
+/// A light that follows the camera around.
+#[derive(Component)]
+struct CameraLight;
+
+fn spawn_lights(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    theme: Res<CurrentTheme>,
+    camera: Query<(Entity, &Transform), With<Camera3d>>,
+    lights: Query<Entity, With<CameraLight>>,
+) {
+    // We come back into the Playing state after every level, or after pausing,
+    // so only start over if the theme has new lights
+    if !lights.is_empty() && !theme.is_changed() {
+        return;
+    }
+
+    for light in &lights {
+        commands.entity(light).despawn_recursive();
+    }
+
     for (camera, camera_transform) in &camera {
+        let world_to_camera = camera_transform.compute_affine().inverse();
+
         commands
             .entity(camera)
             // Without this, the child can't be considered visible either
             .insert(VisibilityBundle::default())
             .with_children(|parent| {
-                // For a point light we don't care about scale/rotation
-                let translation = Vec3::new(3.0, 4.0, 7.0) - camera_transform.translation;
-
-                // Add the point light as a child of the camera, to give the illusion
-                // we are rotating the cube relative to the scene, but actually we're
-                // just moving/rotating the camera around the cube.
-                parent.spawn(PointLightBundle {
-                    point_light: PointLight {
-                        intensity: 1500.0,
-                        shadows_enabled: true,
-                        ..default()
-                    },
-                    transform: Transform::from_translation(translation),
-                    ..default()
-                });
+                for light in &theme.lights {
+                    // Put the light where the theme says in world space, however the
+                    // camera happens to be turned when the lights are (re)spawned
+                    let translation = world_to_camera.transform_point3(light.position.into());
+
+                    // Add the point light as a child of the camera, to give the illusion
+                    // we are rotating the cube relative to the scene, but actually we're
+                    // just moving/rotating the camera around the cube.
+                    parent.spawn((
+                        CameraLight,
+                        PointLightBundle {
+                            point_light: PointLight {
+                                color: light.color,
+                                intensity: light.intensity,
+                                shadows_enabled: settings.graphics.quality.shadows(),
+                                ..default()
+                            },
+                            transform: Transform::from_translation(translation),
+                            ..default()
+                        },
+                    ));
+                }
             });
     }
 }
 
+/// How far the cube turns per logical pixel dragged, before sensitivity.
+const DRAG_RADIANS_PER_PIXEL: f32 = 0.01;
+
 fn rotate_camera(
     time: Res<Time>,
     actions: Res<Actions>,
+    settings: Res<Settings>,
     cube: Query<&Transform, With<CubeFrame>>,
     mut camera: Query<&mut Transform, (With<Camera>, Without<CubeFrame>)>,
 ) {
-    let Some(rotation) = actions.player_rotation else {
-        return;
-    };
+    let camera_settings = &settings.camera;
+
+    let mut angles = Vec2::ZERO;
+
+    if let Some(rotation) = actions.player_rotation {
+        angles += rotation * TAU * camera_settings.rotation_speed * time.delta_seconds();
+    }
+
+    if let Some(drag) = actions.orbit_drag {
+        angles += drag * DRAG_RADIANS_PER_PIXEL * camera_settings.drag_sensitivity;
+    }
 
-    let speed = tweak!(0.4);
+    if angles == Vec2::ZERO {
+        return;
+    }
 
-    let rpms = TAU * speed * time.delta_seconds();
+    let angles = camera_settings.orient(angles);
 
     for cube_transform in &cube {
         for mut camera_transform in &mut camera {
-            // TODO: option for inverting the arrow controls? Click+drag would
-            // be much easier at the end of the day but trickier to implement.
-            // Maybe easier with newer bevy_mod_picking now?
-            let rotation = Quat::from_axis_angle(camera_transform.local_y(), -rotation.x * rpms)
-                * Quat::from_axis_angle(camera_transform.local_x(), rotation.y * rpms);
+            let rotation = Quat::from_axis_angle(camera_transform.local_y(), -angles.x)
+                * Quat::from_axis_angle(camera_transform.local_x(), angles.y);
 
             // We could probably just rotate around the origin, but if the cube ever moves
             // this should handle it better I think
@@ -76,3 +137,40 @@ fn rotate_camera(
         }
     }
 }
+
+/// About as close as [`MIN_CAMERA_DISTANCE`] gets with a perspective projection.
+const MIN_ORTHO_SCALE: f32 = 0.45;
+
+fn zoom_camera(
+    actions: Res<Actions>,
+    framing: Res<CameraFraming>,
+    cube: Query<&Transform, With<CubeFrame>>,
+    mut camera: Query<(&mut Transform, &mut Projection), (With<Camera>, Without<CubeFrame>)>,
+) {
+    let Some(zoom) = actions.zoom else {
+        return;
+    };
+
+    // Don't let the cube get lost in the distance either
+    let max_distance = 2.0 * framing.distance;
+
+    for cube_transform in &cube {
+        for (mut camera_transform, mut projection) in &mut camera {
+            // Moving an orthographic camera closer doesn't make anything bigger,
+            // so zoom by changing how much of the world fits on screen instead.
+            if let Projection::Orthographic(orthographic) = &mut *projection {
+                // big cubes might already be framed closer than the usual minimum
+                let min_scale = MIN_ORTHO_SCALE.min(framing.ortho_scale);
+                let max_scale = 2.0 * framing.ortho_scale;
+                orthographic.scale = (orthographic.scale * zoom).clamp(min_scale, max_scale);
+                continue;
+            }
+
+            let offset = camera_transform.translation - cube_transform.translation;
+            let distance = (offset.length() * zoom).clamp(MIN_CAMERA_DISTANCE, max_distance);
+
+            camera_transform.translation =
+                cube_transform.translation + offset.normalize_or_zero() * distance;
+        }
+    }
+}
@@ -0,0 +1,161 @@
+//! Keeping the cube nicely in view, no matter the level size or window shape.
+
+use bevy::prelude::*;
+use bevy::render::camera::ScalingMode;
+use bevy::window::{PrimaryWindow, WindowResized};
+
+use crate::cubes::{BlockCount, CubeFrame};
+use crate::menu::menu_camera_transform;
+use crate::settings::{ProjectionMode, Settings};
+
+/// Radius around the cube's origin that should fit on screen. The frame is a
+/// 2x2x2 box, so this is its bounding sphere plus some breathing room.
+const FRAMED_RADIUS: f32 = 1.3 * 1.732;
+
+/// The smallest a block should get on screen, in logical pixels. Past this point
+/// we stop trying to fit the whole cube in view, and let the player rotate or zoom
+/// out to see the rest instead.
+const MIN_BLOCK_PIXELS: f32 = 24.0;
+
+/// Never get closer than this, or the camera ends up inside the frame.
+pub const MIN_CAMERA_DISTANCE: f32 = 2.5;
+
+/// How far away the camera "should" be for the current level and window.
+#[derive(Resource)]
+pub struct CameraFraming {
+    /// Distance from the cube when using a perspective projection.
+    pub distance: f32,
+    /// Scale to use for an orthographic projection. The camera distance doesn't
+    /// make any difference to what's visible there.
+    pub ortho_scale: f32,
+}
+
+impl Default for CameraFraming {
+    fn default() -> Self {
+        Self {
+            distance: menu_camera_transform().translation.length(),
+            ortho_scale: 1.0,
+        }
+    }
+}
+
+/// An orthographic projection that fits [`FRAMED_RADIUS`] on screen at a scale of 1.
+pub fn orthographic_projection(scale: f32) -> OrthographicProjection {
+    OrthographicProjection {
+        scale,
+        scaling_mode: ScalingMode::AutoMin {
+            min_width: 2.0 * FRAMED_RADIUS,
+            min_height: 2.0 * FRAMED_RADIUS,
+        },
+        ..default()
+    }
+}
+
+fn framing_distance(block_count: &BlockCount, fov: f32, window: &Window) -> f32 {
+    let half_fov = fov / 2.0;
+    let aspect_ratio = window.width() / window.height();
+
+    // Fit to whichever direction is narrower, so tall phone screens work too
+    let half_fov_horizontal = (half_fov.tan() * aspect_ratio).atan();
+    let fit_distance = FRAMED_RADIUS / half_fov.min(half_fov_horizontal).sin();
+
+    // How far away a block would be exactly MIN_BLOCK_PIXELS tall
+    let block_size = block_count.block_size();
+    let legible_distance = block_size * window.height() / (2.0 * half_fov.tan() * MIN_BLOCK_PIXELS);
+
+    fit_distance.min(legible_distance).max(MIN_CAMERA_DISTANCE)
+}
+
+/// The orthographic equivalent of [`framing_distance`]. A scale of 1 already fits
+/// [`FRAMED_RADIUS`] to the window's shorter side, so this only ever zooms in.
+fn framing_ortho_scale(block_count: &BlockCount, window: &Window) -> f32 {
+    let shorter_side = window.width().min(window.height());
+
+    // The scale at which a block would be exactly MIN_BLOCK_PIXELS across
+    let block_size = block_count.block_size();
+    let legible_scale = block_size * shorter_side / (2.0 * FRAMED_RADIUS * MIN_BLOCK_PIXELS);
+
+    legible_scale.min(1.0)
+}
+
+/// Frame the camera around each new cube, and adjust whenever the window changes
+/// shape. Resizing keeps whatever zoom the player had chosen relative to the framing.
+#[allow(clippy::type_complexity)]
+pub fn frame_camera(
+    mut framing: ResMut<CameraFraming>,
+    mut resized: EventReader<WindowResized>,
+    new_cube: Query<(), Added<CubeFrame>>,
+    cube: Query<&Transform, With<CubeFrame>>,
+    block_count: Res<BlockCount>,
+    window: Query<&Window, With<PrimaryWindow>>,
+    mut camera: Query<(&mut Transform, &mut Projection), (With<Camera3d>, Without<CubeFrame>)>,
+) {
+    // Always drain the resize events, so they don't get handled again next frame
+    let was_resized = resized.read().last().is_some();
+    let is_new_cube = !new_cube.is_empty();
+    if !is_new_cube && !was_resized {
+        return;
+    }
+
+    let Ok(window) = window.get_single() else {
+        return;
+    };
+
+    // e.g. minimized, nothing sensible to frame against
+    if window.width() <= 0.0 || window.height() <= 0.0 {
+        return;
+    }
+
+    for cube_transform in &cube {
+        for (mut camera_transform, mut projection) in &mut camera {
+            let fov = match &*projection {
+                Projection::Perspective(perspective) => perspective.fov,
+                Projection::Orthographic(_) => PerspectiveProjection::default().fov,
+            };
+
+            let distance = framing_distance(&block_count, fov, window);
+            let ortho_scale = framing_ortho_scale(&block_count, window);
+
+            let offset = camera_transform.translation - cube_transform.translation;
+            let new_offset = if is_new_cube {
+                offset.normalize_or_zero() * distance
+            } else {
+                offset * distance / framing.distance
+            };
+            camera_transform.translation = cube_transform.translation + new_offset;
+
+            if let Projection::Orthographic(orthographic) = &mut *projection {
+                orthographic.scale = if is_new_cube {
+                    ortho_scale
+                } else {
+                    orthographic.scale * ortho_scale / framing.ortho_scale
+                };
+            }
+
+            framing.distance = distance;
+            framing.ortho_scale = ortho_scale;
+        }
+    }
+}
+
+/// Swap the camera's projection whenever the setting for it changes. The picking
+/// raycasts go through [`Camera::viewport_to_world`], so they keep working either way.
+pub fn apply_projection_mode(
+    settings: Res<Settings>,
+    framing: Res<CameraFraming>,
+    mut camera: Query<&mut Projection, With<Camera3d>>,
+) {
+    for mut projection in &mut camera {
+        let is_orthographic = matches!(*projection, Projection::Orthographic(_));
+
+        match settings.camera.projection {
+            ProjectionMode::Perspective if is_orthographic => {
+                *projection = PerspectiveProjection::default().into();
+            }
+            ProjectionMode::Orthographic if !is_orthographic => {
+                *projection = orthographic_projection(framing.ortho_scale).into();
+            }
+            _ => {}
+        }
+    }
+}
@@ -0,0 +1,120 @@
+//! Smoothly swinging the camera around to a fixed viewpoint, for lining up a
+//! face of the cube or getting back to a familiar angle.
+
+use bevy::prelude::*;
+
+use crate::actions::{Actions, CameraView};
+use crate::cubes::CubeFrame;
+use crate::menu::menu_camera_transform;
+use crate::settings::Settings;
+
+use super::framing::CameraFraming;
+
+const SNAP_SECONDS: f32 = 0.4;
+
+/// An in-progress swing of the camera around the cube.
+#[derive(Component)]
+pub struct CameraSnap {
+    from_rotation: Quat,
+    from_distance: f32,
+    to_rotation: Quat,
+    to_distance: f32,
+    timer: Timer,
+}
+
+/// The camera pose for a view, relative to the cube's origin.
+fn view_transform(view: CameraView, distance: f32, framing: &CameraFraming) -> Transform {
+    let direction = match view {
+        CameraView::Reset => {
+            let mut transform = menu_camera_transform();
+            transform.translation = transform.translation.normalize() * framing.distance;
+            return transform;
+        }
+        CameraView::Face(direction) | CameraView::Corner(direction) => direction.normalize(),
+    };
+
+    // Looking straight down (or up) needs a different "up", and keeping the
+    // front face at the bottom of the screen seems the least disorienting.
+    let up = if direction.x == 0.0 && direction.z == 0.0 {
+        Vec3::NEG_Z * direction.y
+    } else {
+        Vec3::Y
+    };
+
+    Transform::from_translation(direction * distance).looking_at(Vec3::ZERO, up)
+}
+
+pub fn start_camera_snap(
+    mut commands: Commands,
+    actions: Res<Actions>,
+    framing: Res<CameraFraming>,
+    settings: Res<Settings>,
+    cube: Query<&Transform, With<CubeFrame>>,
+    camera: Query<(Entity, &Transform), (With<Camera>, Without<CubeFrame>)>,
+) {
+    let Some(view) = actions.camera_view else {
+        return;
+    };
+
+    // Jump straight there instead of swinging the whole view around
+    let seconds = if settings.accessibility.reduced_motion {
+        0.0
+    } else {
+        SNAP_SECONDS
+    };
+
+    for cube_transform in &cube {
+        for (entity, camera_transform) in &camera {
+            let distance = camera_transform
+                .translation
+                .distance(cube_transform.translation);
+
+            let target = view_transform(view, distance, &framing);
+
+            commands.entity(entity).insert(CameraSnap {
+                from_rotation: camera_transform.rotation,
+                from_distance: distance,
+                to_rotation: target.rotation,
+                to_distance: target.translation.length(),
+                timer: Timer::from_seconds(seconds, TimerMode::Once),
+            });
+        }
+    }
+}
+
+pub fn animate_camera_snap(
+    mut commands: Commands,
+    time: Res<Time>,
+    actions: Res<Actions>,
+    cube: Query<&Transform, With<CubeFrame>>,
+    mut camera: Query<(Entity, &mut Transform, &mut CameraSnap), Without<CubeFrame>>,
+) {
+    for cube_transform in &cube {
+        for (entity, mut camera_transform, mut snap) in &mut camera {
+            // Let the player take over again if they start moving the cube themselves
+            if actions.player_rotation.is_some() || actions.orbit_drag.is_some() {
+                commands.entity(entity).remove::<CameraSnap>();
+                continue;
+            }
+
+            snap.timer.tick(time.delta());
+
+            // smoothstep, so it eases in and out of the swing
+            let t = snap.timer.percent();
+            let t = t * t * (3.0 - 2.0 * t);
+
+            let rotation = snap.from_rotation.slerp(snap.to_rotation, t);
+            let distance = snap.from_distance + (snap.to_distance - snap.from_distance) * t;
+
+            // The camera always looks at the cube, so it sits "behind" itself
+            // along its own view direction.
+            camera_transform.rotation = rotation;
+            camera_transform.translation =
+                cube_transform.translation + rotation * Vec3::Z * distance;
+
+            if snap.timer.finished() {
+                commands.entity(entity).remove::<CameraSnap>();
+            }
+        }
+    }
+}
@@ -0,0 +1,88 @@
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::storage;
+
+const PROGRESS_KEY: &str = "progress";
+
+/// How many levels are offered on the level select screen. Continuing past the
+/// last one still works, they just get too big to be worth listing.
+pub const LEVEL_COUNT: i16 = 10;
+
+pub struct ProgressPlugin;
+
+/// This plugin keeps track of which levels the player has beaten, and how well.
+impl Plugin for ProgressPlugin {
+    fn build(&self, app: &mut App) {
+        let progress: Progress = storage::load(PROGRESS_KEY).unwrap_or_default();
+
+        app.insert_resource(progress)
+            .add_systems(Last, save_progress.run_if(resource_changed::<Progress>()));
+    }
+}
+
+/// The player's records for every level they've finished, keyed by `BlockCount`.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Progress {
+    pub levels: BTreeMap<i16, LevelRecord>,
+    /// Whether the tutorial has been finished or skipped, so it isn't shown again.
+    pub tutorial_completed: bool,
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct LevelRecord {
+    /// Fastest completion, in seconds.
+    pub best_time: Option<f32>,
+    /// Fewest blocks pushed by the player to finish the level.
+    pub best_moves: Option<u32>,
+}
+
+impl Progress {
+    pub fn is_completed(&self, level: i16) -> bool {
+        self.levels.contains_key(&level)
+    }
+
+    /// The first level is always open, every other one needs the level before it.
+    pub fn is_unlocked(&self, level: i16) -> bool {
+        level <= 1 || self.is_completed(level - 1)
+    }
+
+    /// Remember a completed level, keeping whichever records are best.
+    pub fn record(&mut self, level: i16, time: f32, moves: u32) -> NewBests {
+        let record = self.levels.entry(level).or_default();
+
+        // The first clear sets the records, but doesn't beat any
+        let new_bests = NewBests {
+            time: record.best_time.map_or(false, |best| time < best),
+            moves: record.best_moves.map_or(false, |best| moves < best),
+        };
+
+        record.best_time = Some(record.best_time.map_or(time, |best| best.min(time)));
+        record.best_moves = Some(record.best_moves.map_or(moves, |best| best.min(moves)));
+
+        new_bests
+    }
+}
+
+/// Which of a level's records were just beaten.
+#[derive(Default, Clone, Copy, Debug)]
+pub struct NewBests {
+    pub time: bool,
+    pub moves: bool,
+}
+
+/// Format a number of seconds like `1:05`.
+pub fn format_time(seconds: f32) -> String {
+    // truncating is intentional, nobody needs to see partial seconds
+    let seconds = seconds.max(0.0) as u32;
+
+    format!("{}:{:02}", seconds / 60, seconds % 60)
+}
+
+fn save_progress(progress: Res<Progress>) {
+    storage::save(PROGRESS_KEY, &*progress);
+}
@@ -0,0 +1,296 @@
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cubes::ColorPalette;
+use crate::localization::Language;
+use crate::storage;
+use crate::theme::Theme;
+
+mod screen;
+
+pub use self::screen::{SettingsButton, SettingsScreen};
+
+const SETTINGS_KEY: &str = "settings";
+
+pub struct SettingsPlugin;
+
+/// This plugin owns the player's preferences. They are loaded once at startup
+/// and saved again whenever they change.
+impl Plugin for SettingsPlugin {
+    fn build(&self, app: &mut App) {
+        let settings = storage::load::<Settings>(SETTINGS_KEY)
+            .unwrap_or_default()
+            .sanitized();
+
+        app.insert_resource(settings)
+            .add_plugins(screen::SettingsScreenPlugin)
+            .add_systems(
+                Update,
+                (apply_graphics_settings, apply_accessibility_settings)
+                    .run_if(resource_changed::<Settings>()),
+            )
+            .add_systems(Last, save_settings.run_if(resource_changed::<Settings>()));
+    }
+}
+
+/// All player-facing preferences. Missing fields fall back to their defaults,
+/// so older save files keep working as new settings are added.
+#[derive(Resource, Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct Settings {
+    pub gameplay: GameplaySettings,
+    pub camera: CameraSettings,
+    pub graphics: GraphicsSettings,
+    pub accessibility: AccessibilitySettings,
+    pub language: Language,
+    pub theme: Theme,
+}
+
+impl Settings {
+    /// Bring every number back within what the settings screen allows, in case
+    /// the file was edited by hand or saved by an older version. Anything that
+    /// isn't a number at all goes back to its default.
+    fn sanitized(mut self) -> Self {
+        let defaults = Self::default();
+
+        sanitize(
+            &mut self.gameplay.animation_speed,
+            defaults.gameplay.animation_speed,
+            GameplaySettings::ANIMATION_SPEED,
+        );
+        sanitize(
+            &mut self.gameplay.cascade_delay,
+            defaults.gameplay.cascade_delay,
+            GameplaySettings::CASCADE_DELAY,
+        );
+        sanitize(
+            &mut self.gameplay.celebration_length,
+            defaults.gameplay.celebration_length,
+            GameplaySettings::CELEBRATION_LENGTH,
+        );
+        sanitize(
+            &mut self.camera.rotation_speed,
+            defaults.camera.rotation_speed,
+            CameraSettings::ROTATION_SPEED,
+        );
+        sanitize(
+            &mut self.camera.drag_sensitivity,
+            defaults.camera.drag_sensitivity,
+            CameraSettings::DRAG_SENSITIVITY,
+        );
+        sanitize(
+            &mut self.accessibility.ui_scale,
+            defaults.accessibility.ui_scale,
+            AccessibilitySettings::UI_SCALE,
+        );
+
+        self
+    }
+}
+
+fn sanitize(value: &mut f32, default: f32, range: RangeInclusive<f32>) {
+    *value = if value.is_finite() {
+        value.clamp(*range.start(), *range.end())
+    } else {
+        default
+    };
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct GameplaySettings {
+    /// Pushing a block in knocks out its parent instead of its children, which
+    /// can undo a lot more progress at once.
+    pub hard_mode: bool,
+    /// Multiplier for how fast blocks move in and out.
+    pub animation_speed: f32,
+    /// Draw arrows between blocks that knock each other out.
+    pub show_connections: bool,
+    /// Outline the blocks that pushing the hovered block would knock out.
+    pub preview_knock_outs: bool,
+    /// Seconds between each step of a cascade, so it can be followed by eye.
+    /// Zero knocks everything out at once.
+    pub cascade_delay: f32,
+    /// Seconds to celebrate a solved level before showing the results. Zero
+    /// goes straight to the results.
+    pub celebration_length: f32,
+}
+
+impl Default for GameplaySettings {
+    fn default() -> Self {
+        Self {
+            hard_mode: false,
+            animation_speed: 1.0,
+            show_connections: false,
+            preview_knock_outs: false,
+            cascade_delay: 0.0,
+            celebration_length: 2.0,
+        }
+    }
+}
+
+impl GameplaySettings {
+    pub const ANIMATION_SPEED: RangeInclusive<f32> = 0.5..=5.0;
+    pub const CASCADE_DELAY: RangeInclusive<f32> = 0.0..=0.5;
+    pub const CELEBRATION_LENGTH: RangeInclusive<f32> = 0.0..=5.0;
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct CameraSettings {
+    /// Flip the horizontal direction of keyboard, gamepad and drag rotation.
+    pub invert_x: bool,
+    /// Flip the vertical direction of keyboard, gamepad and drag rotation.
+    pub invert_y: bool,
+    /// Rotations per second when holding a direction key or stick.
+    pub rotation_speed: f32,
+    /// Multiplier for how far the cube turns when dragging it around.
+    pub drag_sensitivity: f32,
+    /// How the cube is drawn on screen.
+    pub projection: ProjectionMode,
+}
+
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum ProjectionMode {
+    /// Things further away look smaller, like normal.
+    #[default]
+    Perspective,
+    /// Every block looks the same size no matter how far away it is, which makes
+    /// it easier to line up rows of blocks at the edges of the cube.
+    Orthographic,
+}
+
+impl Default for CameraSettings {
+    fn default() -> Self {
+        Self {
+            invert_x: false,
+            invert_y: false,
+            rotation_speed: 0.4,
+            drag_sensitivity: 1.0,
+            projection: ProjectionMode::default(),
+        }
+    }
+}
+
+impl CameraSettings {
+    pub const ROTATION_SPEED: RangeInclusive<f32> = 0.1..=1.5;
+    pub const DRAG_SENSITIVITY: RangeInclusive<f32> = 0.25..=3.0;
+
+    /// Apply the inversion settings to a rotation input.
+    pub fn orient(&self, rotation: Vec2) -> Vec2 {
+        let sign = |invert: bool| if invert { -1.0 } else { 1.0 };
+        rotation * Vec2::new(sign(self.invert_x), sign(self.invert_y))
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Clone, Debug)]
+#[serde(default)]
+pub struct GraphicsSettings {
+    pub quality: GraphicsQuality,
+}
+
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum GraphicsQuality {
+    /// No antialiasing or shadows, for slow machines and browsers.
+    Low,
+    /// Antialiasing, but no shadows.
+    Medium,
+    #[default]
+    High,
+}
+
+impl GraphicsQuality {
+    pub fn msaa(self) -> Msaa {
+        match self {
+            Self::Low => Msaa::Off,
+            // WebGL only supports 1 or 4 samples, so don't bother going higher
+            Self::Medium | Self::High => Msaa::Sample4,
+        }
+    }
+
+    pub fn shadows(self) -> bool {
+        self == Self::High
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(default)]
+pub struct AccessibilitySettings {
+    /// Multiplier for the size of all UI, including text.
+    pub ui_scale: f32,
+    /// Use darker buttons so their labels stand out more.
+    pub high_contrast: bool,
+    /// Skip or shorten animations that move the whole view around.
+    pub reduced_motion: bool,
+    /// Colors for blocks and outlines, with options for color vision deficiencies.
+    pub palette: ColorPalette,
+}
+
+impl Default for AccessibilitySettings {
+    fn default() -> Self {
+        Self {
+            ui_scale: 1.0,
+            high_contrast: false,
+            reduced_motion: false,
+            palette: ColorPalette::default(),
+        }
+    }
+}
+
+impl AccessibilitySettings {
+    pub const UI_SCALE: RangeInclusive<f32> = 0.75..=2.0;
+}
+
+fn apply_graphics_settings(
+    settings: Res<Settings>,
+    mut msaa: ResMut<Msaa>,
+    mut lights: Query<&mut PointLight>,
+) {
+    let quality = settings.graphics.quality;
+
+    *msaa = quality.msaa();
+
+    for mut light in &mut lights {
+        light.shadows_enabled = quality.shadows();
+    }
+}
+
+fn apply_accessibility_settings(settings: Res<Settings>, mut ui_scale: ResMut<UiScale>) {
+    ui_scale.0 = f64::from(settings.accessibility.ui_scale);
+}
+
+fn save_settings(settings: Res<Settings>) {
+    storage::save(SETTINGS_KEY, &*settings);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sanitize_loaded_numbers() {
+        let mut settings = Settings::default();
+        settings.gameplay.animation_speed = 0.0;
+        settings.gameplay.cascade_delay = f32::NAN;
+        settings.gameplay.celebration_length = f32::INFINITY;
+        settings.camera.rotation_speed = -1.0;
+        settings.accessibility.ui_scale = 100.0;
+
+        let settings = settings.sanitized();
+        let defaults = Settings::default();
+
+        assert_eq!(settings.gameplay.animation_speed, 0.5);
+        assert_eq!(
+            settings.gameplay.cascade_delay,
+            defaults.gameplay.cascade_delay
+        );
+        assert_eq!(
+            settings.gameplay.celebration_length,
+            defaults.gameplay.celebration_length
+        );
+        assert_eq!(settings.camera.rotation_speed, 0.1);
+        assert_eq!(settings.accessibility.ui_scale, 2.0);
+    }
+}
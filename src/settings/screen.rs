@@ -0,0 +1,487 @@
+//! The settings screen, which can be opened on top of the main menu or the pause menu.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use bevy::ui::FocusPolicy;
+
+use crate::cubes::ColorPalette;
+use crate::loading::FontAssets;
+use crate::localization::{Language, LocalizedText};
+use crate::menu::{spawn_button, ButtonColors};
+use crate::theme::Theme;
+
+use super::{
+    AccessibilitySettings, CameraSettings, GameplaySettings, GraphicsQuality, ProjectionMode,
+    Settings,
+};
+
+pub struct SettingsScreenPlugin;
+
+impl Plugin for SettingsScreenPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_state::<SettingsScreen>()
+            .add_systems(OnEnter(SettingsScreen::Open), setup_settings_screen)
+            .add_systems(OnExit(SettingsScreen::Open), cleanup_settings_screen)
+            .add_systems(
+                Update,
+                (
+                    click_settings_button.run_if(in_state(SettingsScreen::Closed)),
+                    (click_adjust_buttons, update_setting_values, close_settings)
+                        .chain()
+                        .run_if(in_state(SettingsScreen::Open)),
+                ),
+            );
+    }
+}
+
+/// Whether the settings screen is showing. This is kept separate from `GameState`
+/// so the screen can sit on top of whichever menu it was opened from.
+#[derive(States, Default, Clone, Eq, PartialEq, Debug, Hash)]
+pub enum SettingsScreen {
+    #[default]
+    Closed,
+    Open,
+}
+
+/// Opens the settings screen when clicked.
+#[derive(Component)]
+pub struct SettingsButton;
+
+#[derive(Component)]
+struct SettingsRoot;
+
+#[derive(Component)]
+struct BackButton;
+
+/// Steps one of the [`ENTRIES`] up or down.
+#[derive(Component)]
+struct AdjustButton {
+    entry: usize,
+    direction: f32,
+}
+
+/// Shows the current value of one of the [`ENTRIES`].
+#[derive(Component)]
+struct SettingValue(usize);
+
+struct SettingEntry {
+    label: &'static str,
+    value: fn(&Settings) -> LocalizedText,
+    /// Change the setting by one step, up or down depending on the sign.
+    adjust: fn(&mut Settings, f32),
+}
+
+const ENTRIES: &[SettingEntry] = &[
+    SettingEntry {
+        label: "settings.language",
+        // Language names are always shown in their own language
+        value: |settings| {
+            LocalizedText::with_args("settings.language_name", [settings.language.native_name()])
+        },
+        adjust: |settings, direction| {
+            let languages = Language::ALL;
+            let current = languages
+                .iter()
+                .position(|&language| language == settings.language)
+                .unwrap_or_default();
+
+            // Wrap around, there's no natural order to languages
+            let next = (current as isize + direction.signum() as isize)
+                .rem_euclid(languages.len() as isize);
+
+            settings.language = languages[next as usize];
+        },
+    },
+    SettingEntry {
+        label: "settings.theme",
+        value: |settings| LocalizedText::new(settings.theme.label()),
+        adjust: |settings, direction| {
+            let themes = Theme::ALL;
+            let current = themes
+                .iter()
+                .position(|&theme| theme == settings.theme)
+                .unwrap_or_default();
+
+            let next =
+                (current as isize + direction.signum() as isize).rem_euclid(themes.len() as isize);
+
+            settings.theme = themes[next as usize];
+        },
+    },
+    SettingEntry {
+        label: "settings.hard_mode",
+        value: |settings| on_off(settings.gameplay.hard_mode),
+        adjust: |settings, _| toggle(&mut settings.gameplay.hard_mode),
+    },
+    SettingEntry {
+        label: "settings.animation_speed",
+        value: |settings| multiplier(settings.gameplay.animation_speed),
+        adjust: |settings, direction| {
+            step(
+                &mut settings.gameplay.animation_speed,
+                direction,
+                0.25,
+                GameplaySettings::ANIMATION_SPEED,
+            );
+        },
+    },
+    SettingEntry {
+        label: "settings.show_connections",
+        value: |settings| on_off(settings.gameplay.show_connections),
+        adjust: |settings, _| toggle(&mut settings.gameplay.show_connections),
+    },
+    SettingEntry {
+        label: "settings.preview_pushes",
+        value: |settings| on_off(settings.gameplay.preview_knock_outs),
+        adjust: |settings, _| toggle(&mut settings.gameplay.preview_knock_outs),
+    },
+    SettingEntry {
+        label: "settings.cascade_delay",
+        value: |settings| match settings.gameplay.cascade_delay {
+            delay if delay > 0.0 => {
+                LocalizedText::with_args("settings.seconds", [format!("{delay:.2}")])
+            }
+            _ => on_off(false),
+        },
+        adjust: |settings, direction| {
+            step(
+                &mut settings.gameplay.cascade_delay,
+                direction,
+                0.05,
+                GameplaySettings::CASCADE_DELAY,
+            );
+        },
+    },
+    SettingEntry {
+        label: "settings.celebration",
+        value: |settings| match settings.gameplay.celebration_length {
+            length if length > 0.0 => {
+                LocalizedText::with_args("settings.seconds", [format!("{length:.1}")])
+            }
+            _ => on_off(false),
+        },
+        adjust: |settings, direction| {
+            step(
+                &mut settings.gameplay.celebration_length,
+                direction,
+                0.5,
+                GameplaySettings::CELEBRATION_LENGTH,
+            );
+        },
+    },
+    SettingEntry {
+        label: "settings.invert_x",
+        value: |settings| on_off(settings.camera.invert_x),
+        adjust: |settings, _| toggle(&mut settings.camera.invert_x),
+    },
+    SettingEntry {
+        label: "settings.invert_y",
+        value: |settings| on_off(settings.camera.invert_y),
+        adjust: |settings, _| toggle(&mut settings.camera.invert_y),
+    },
+    SettingEntry {
+        label: "settings.rotation_speed",
+        value: |settings| {
+            LocalizedText::with_args(
+                "settings.number",
+                [format!("{:.1}", settings.camera.rotation_speed)],
+            )
+        },
+        adjust: |settings, direction| {
+            step(
+                &mut settings.camera.rotation_speed,
+                direction,
+                0.1,
+                CameraSettings::ROTATION_SPEED,
+            );
+        },
+    },
+    SettingEntry {
+        label: "settings.drag_sensitivity",
+        value: |settings| multiplier(settings.camera.drag_sensitivity),
+        adjust: |settings, direction| {
+            step(
+                &mut settings.camera.drag_sensitivity,
+                direction,
+                0.25,
+                CameraSettings::DRAG_SENSITIVITY,
+            );
+        },
+    },
+    SettingEntry {
+        label: "settings.projection",
+        value: |settings| {
+            LocalizedText::new(match settings.camera.projection {
+                ProjectionMode::Perspective => "settings.perspective",
+                ProjectionMode::Orthographic => "settings.orthographic",
+            })
+        },
+        adjust: |settings, _| {
+            settings.camera.projection = match settings.camera.projection {
+                ProjectionMode::Perspective => ProjectionMode::Orthographic,
+                ProjectionMode::Orthographic => ProjectionMode::Perspective,
+            };
+        },
+    },
+    SettingEntry {
+        label: "settings.graphics",
+        value: |settings| {
+            LocalizedText::new(match settings.graphics.quality {
+                GraphicsQuality::Low => "settings.low",
+                GraphicsQuality::Medium => "settings.medium",
+                GraphicsQuality::High => "settings.high",
+            })
+        },
+        adjust: |settings, direction| {
+            const QUALITIES: [GraphicsQuality; 3] = [
+                GraphicsQuality::Low,
+                GraphicsQuality::Medium,
+                GraphicsQuality::High,
+            ];
+
+            let current = QUALITIES
+                .iter()
+                .position(|&quality| quality == settings.graphics.quality)
+                .unwrap_or_default();
+
+            let next = if direction < 0.0 {
+                current.saturating_sub(1)
+            } else {
+                (current + 1).min(QUALITIES.len() - 1)
+            };
+
+            settings.graphics.quality = QUALITIES[next];
+        },
+    },
+    SettingEntry {
+        label: "settings.ui_scale",
+        value: |settings| multiplier(settings.accessibility.ui_scale),
+        adjust: |settings, direction| {
+            step(
+                &mut settings.accessibility.ui_scale,
+                direction,
+                0.25,
+                AccessibilitySettings::UI_SCALE,
+            );
+        },
+    },
+    SettingEntry {
+        label: "settings.high_contrast",
+        value: |settings| on_off(settings.accessibility.high_contrast),
+        adjust: |settings, _| toggle(&mut settings.accessibility.high_contrast),
+    },
+    SettingEntry {
+        label: "settings.reduced_motion",
+        value: |settings| on_off(settings.accessibility.reduced_motion),
+        adjust: |settings, _| toggle(&mut settings.accessibility.reduced_motion),
+    },
+    SettingEntry {
+        label: "settings.palette",
+        value: |settings| LocalizedText::new(settings.accessibility.palette.label()),
+        adjust: |settings, direction| {
+            let palettes = ColorPalette::ALL;
+            let current = palettes
+                .iter()
+                .position(|&palette| palette == settings.accessibility.palette)
+                .unwrap_or_default();
+
+            let next = (current as isize + direction.signum() as isize)
+                .rem_euclid(palettes.len() as isize);
+
+            settings.accessibility.palette = palettes[next as usize];
+        },
+    },
+];
+
+fn on_off(value: bool) -> LocalizedText {
+    LocalizedText::new(if value { "settings.on" } else { "settings.off" })
+}
+
+fn multiplier(value: f32) -> LocalizedText {
+    LocalizedText::with_args("settings.multiplier", [format!("{value:.2}")])
+}
+
+fn toggle(value: &mut bool) {
+    *value = !*value;
+}
+
+/// Move a "slider" by one step, snapping to whole steps so that repeated
+/// float additions don't drift.
+fn step(value: &mut f32, direction: f32, step: f32, range: RangeInclusive<f32>) {
+    let stepped = ((*value + direction.signum() * step) / step).round() * step;
+    *value = stepped.clamp(*range.start(), *range.end());
+}
+
+fn click_settings_button(
+    mut screen: ResMut<NextState<SettingsScreen>>,
+    mut interaction: Query<&Interaction, (Changed<Interaction>, With<SettingsButton>)>,
+) {
+    for interaction in &mut interaction {
+        if let Interaction::Pressed = *interaction {
+            screen.set(SettingsScreen::Open);
+        }
+    }
+}
+
+fn setup_settings_screen(
+    mut commands: Commands,
+    settings: Res<Settings>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = TextStyle {
+        font_size: 26.0,
+        ..font_assets.button_style()
+    };
+
+    let arrow_button = ButtonBundle {
+        style: Style {
+            width: Val::Px(36.0),
+            height: Val::Px(30.0),
+            justify_content: JustifyContent::Center,
+            align_items: AlignItems::Center,
+            ..default()
+        },
+        background_color: button_colors.normal.into(),
+        ..default()
+    };
+
+    commands
+        .spawn((
+            SettingsRoot,
+            NodeBundle {
+                style: Style {
+                    width: Val::Percent(100.0),
+                    height: Val::Percent(100.0),
+                    flex_direction: FlexDirection::Column,
+                    justify_content: JustifyContent::Center,
+                    align_items: AlignItems::Center,
+                    ..default()
+                },
+                background_color: Color::rgba(0.0, 0.0, 0.0, 0.85).into(),
+                // Draw over, and keep clicks away from, whatever menu is underneath
+                focus_policy: FocusPolicy::Block,
+                z_index: ZIndex::Global(10),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent.spawn(LocalizedText::new("menu.settings").bundle(font_assets.button_style()));
+
+            for (index, entry) in ENTRIES.iter().enumerate() {
+                parent
+                    .spawn(NodeBundle {
+                        style: Style {
+                            width: Val::Px(520.0),
+                            height: Val::Px(34.0),
+                            justify_content: JustifyContent::SpaceBetween,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        ..default()
+                    })
+                    .with_children(|parent| {
+                        parent.spawn(LocalizedText::new(entry.label).bundle(text_style.clone()));
+
+                        parent
+                            .spawn(NodeBundle {
+                                style: Style {
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                ..default()
+                            })
+                            .with_children(|parent| {
+                                spawn_arrow(parent, index, -1.0, &text_style, &arrow_button);
+
+                                parent
+                                    .spawn(NodeBundle {
+                                        style: Style {
+                                            width: Val::Px(170.0),
+                                            justify_content: JustifyContent::Center,
+                                            ..default()
+                                        },
+                                        ..default()
+                                    })
+                                    .with_children(|parent| {
+                                        parent.spawn((
+                                            SettingValue(index),
+                                            (entry.value)(&settings).bundle(text_style.clone()),
+                                        ));
+                                    });
+
+                                spawn_arrow(parent, index, 1.0, &text_style, &arrow_button);
+                            });
+                    });
+            }
+
+            spawn_button(
+                parent,
+                BackButton,
+                "common.back",
+                &font_assets,
+                &button_colors,
+            );
+        });
+}
+
+fn spawn_arrow(
+    parent: &mut ChildBuilder,
+    entry: usize,
+    direction: f32,
+    text_style: &TextStyle,
+    arrow_button: &ButtonBundle,
+) {
+    let label = if direction < 0.0 { "<" } else { ">" };
+
+    parent
+        .spawn((AdjustButton { entry, direction }, arrow_button.clone()))
+        .with_children(|parent| {
+            parent.spawn(TextBundle::from_section(label, text_style.clone()));
+        });
+}
+
+fn click_adjust_buttons(
+    mut settings: ResMut<Settings>,
+    interaction: Query<(&Interaction, &AdjustButton), Changed<Interaction>>,
+) {
+    for (interaction, button) in &interaction {
+        if let Interaction::Pressed = *interaction {
+            (ENTRIES[button.entry].adjust)(&mut settings, button.direction);
+        }
+    }
+}
+
+fn update_setting_values(
+    settings: Res<Settings>,
+    mut values: Query<(&mut LocalizedText, &SettingValue)>,
+) {
+    if !settings.is_changed() {
+        return;
+    }
+
+    for (mut text, value) in &mut values {
+        *text = (ENTRIES[value.0].value)(&settings);
+    }
+}
+
+fn close_settings(
+    mut screen: ResMut<NextState<SettingsScreen>>,
+    keyboard_input: Res<Input<KeyCode>>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+) {
+    let back_pressed = interaction
+        .iter()
+        .any(|interaction| *interaction == Interaction::Pressed);
+
+    if back_pressed || keyboard_input.just_pressed(KeyCode::Escape) {
+        screen.set(SettingsScreen::Closed);
+    }
+}
+
+fn cleanup_settings_screen(mut commands: Commands, root: Query<Entity, With<SettingsRoot>>) {
+    for entity in &root {
+        commands.entity(entity).despawn_recursive();
+    }
+}
@@ -0,0 +1,100 @@
+//! Very small key/value persistence for things like player preferences.
+//!
+//! On native targets each key is a RON file in the platform config directory,
+//! and in the browser it's an entry in `localStorage`. Failures are logged
+//! and otherwise ignored, since losing preferences shouldn't stop the game.
+
+use bevy::log;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+
+/// Load a previously saved value, if there is one and it can still be parsed.
+pub fn load<T: DeserializeOwned>(key: &str) -> Option<T> {
+    let contents = backend::read(key)?;
+
+    match ron::from_str(&contents) {
+        Ok(value) => Some(value),
+        Err(err) => {
+            log::warn!("ignoring unreadable saved {key:?}: {err}");
+            None
+        }
+    }
+}
+
+/// Save a value so it can be [`load`]ed in a later session.
+pub fn save<T: Serialize>(key: &str, value: &T) {
+    match ron::ser::to_string_pretty(value, ron::ser::PrettyConfig::default()) {
+        Ok(contents) => backend::write(key, &contents),
+        Err(err) => log::error!("failed to serialize {key:?}: {err}"),
+    }
+}
+
+#[cfg(not(target_family = "wasm"))]
+mod backend {
+    use std::path::PathBuf;
+
+    use bevy::log;
+
+    fn save_dir() -> PathBuf {
+        let base = if cfg!(windows) {
+            std::env::var_os("APPDATA").map(PathBuf::from)
+        } else if cfg!(target_os = "macos") {
+            std::env::var_os("HOME")
+                .map(|home| PathBuf::from(home).join("Library/Application Support"))
+        } else {
+            std::env::var_os("XDG_CONFIG_HOME")
+                .map(PathBuf::from)
+                .or_else(|| {
+                    std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".config"))
+                })
+        };
+
+        base.unwrap_or_default().join("pist-off")
+    }
+
+    fn path(key: &str) -> PathBuf {
+        save_dir().join(key).with_extension("ron")
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        std::fs::read_to_string(path(key)).ok()
+    }
+
+    pub fn write(key: &str, contents: &str) {
+        let path = path(key);
+
+        let result =
+            std::fs::create_dir_all(save_dir()).and_then(|()| std::fs::write(&path, contents));
+        if let Err(err) = result {
+            log::error!("failed to save {}: {err}", path.display());
+        }
+    }
+}
+
+#[cfg(target_family = "wasm")]
+mod backend {
+    use bevy::log;
+
+    fn local_storage() -> Option<web_sys::Storage> {
+        web_sys::window()?.local_storage().ok().flatten()
+    }
+
+    fn item_name(key: &str) -> String {
+        format!("pist-off.{key}")
+    }
+
+    pub fn read(key: &str) -> Option<String> {
+        local_storage()?.get_item(&item_name(key)).ok().flatten()
+    }
+
+    pub fn write(key: &str, contents: &str) {
+        let Some(storage) = local_storage() else {
+            log::warn!("localStorage is unavailable, not saving {key:?}");
+            return;
+        };
+
+        if let Err(err) = storage.set_item(&item_name(key), contents) {
+            log::error!("failed to save {key:?}: {err:?}");
+        }
+    }
+}
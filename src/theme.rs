@@ -0,0 +1,252 @@
+//! The overall look of the game. Every theme is a `assets/themes/<name>.theme.ron`
+//! file describing the frame model, backdrop, lighting and block appearance, and
+//! the one picked in settings gets applied to the scene.
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, AsyncReadExt, LoadContext};
+use bevy::gltf::Gltf;
+use bevy::log;
+use bevy::prelude::*;
+use bevy::utils::BoxedFuture;
+use bevy_asset_loader::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::cubes::{BlockMaterial, ColorPalette, CubeFrame, PushAnimation, StateColors};
+use crate::settings::Settings;
+use crate::GameState;
+
+pub struct ThemePlugin;
+
+/// This plugin loads every theme, and keeps the scene in sync with whichever
+/// one is selected. With the `dev` feature, edits to a theme file show up
+/// while the game is running.
+impl Plugin for ThemePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_asset::<ThemeStyle>()
+            .register_asset_loader(ThemeLoader)
+            .init_resource::<CurrentTheme>()
+            .add_systems(OnExit(GameState::Loading), update_theme)
+            .add_systems(
+                Update,
+                update_theme
+                    .run_if(resource_exists::<ThemeAssets>())
+                    .run_if(
+                        resource_changed::<Settings>()
+                            .or_else(on_event::<AssetEvent<ThemeStyle>>()),
+                    ),
+            )
+            .add_systems(
+                Update,
+                apply_theme.run_if(resource_changed::<CurrentTheme>()),
+            );
+    }
+}
+
+#[derive(Serialize, Deserialize, Default, Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Theme {
+    /// A wooden box on a warm backdrop.
+    #[default]
+    Workshop,
+    Night,
+    Paper,
+}
+
+impl Theme {
+    pub const ALL: [Theme; 3] = [Theme::Workshop, Theme::Night, Theme::Paper];
+
+    /// The key for the theme's name in the settings screen.
+    pub fn label(self) -> &'static str {
+        match self {
+            Self::Workshop => "theme.workshop",
+            Self::Night => "theme.night",
+            Self::Paper => "theme.paper",
+        }
+    }
+}
+
+#[derive(AssetCollection, Resource)]
+pub struct ThemeAssets {
+    #[asset(path = "themes/workshop.theme.ron")]
+    pub workshop: Handle<ThemeStyle>,
+    #[asset(path = "themes/night.theme.ron")]
+    pub night: Handle<ThemeStyle>,
+    #[asset(path = "themes/paper.theme.ron")]
+    pub paper: Handle<ThemeStyle>,
+}
+
+impl ThemeAssets {
+    fn get(&self, theme: Theme) -> &Handle<ThemeStyle> {
+        match theme {
+            Theme::Workshop => &self.workshop,
+            Theme::Night => &self.night,
+            Theme::Paper => &self.paper,
+        }
+    }
+}
+
+/// Everything a theme decides about how the game looks.
+#[derive(Asset, TypePath, Clone, Debug)]
+pub struct ThemeStyle {
+    /// The model the blocks sit inside. It needs a scene named "Scene".
+    pub frame: Handle<Gltf>,
+    pub clear_color: Color,
+    pub ambient_light: AmbientLight,
+    /// Lights that follow the camera around.
+    pub lights: Vec<ThemeLight>,
+    pub block_material: BlockMaterial,
+    /// Outline colors for the default palette. The color vision palettes use
+    /// their own, so they stay readable with any theme.
+    pub outline_colors: StateColors,
+    pub push_animation: PushAnimation,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ThemeLight {
+    /// Where the light starts out, before it starts following the camera.
+    pub position: [f32; 3],
+    pub color: Color,
+    pub intensity: f32,
+}
+
+/// A theme as it's written on disk, with the frame as a path instead of a handle.
+#[derive(Deserialize)]
+struct ThemeFile {
+    frame: String,
+    clear_color: Color,
+    ambient_color: Color,
+    ambient_brightness: f32,
+    lights: Vec<ThemeLight>,
+    #[serde(default)]
+    block_material: BlockMaterial,
+    outline_colors: StateColors,
+    #[serde(default)]
+    push_animation: PushAnimation,
+}
+
+#[derive(Default)]
+struct ThemeLoader;
+
+impl AssetLoader for ThemeLoader {
+    type Asset = ThemeStyle;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    fn load<'a>(
+        &'a self,
+        reader: &'a mut Reader,
+        _settings: &'a Self::Settings,
+        load_context: &'a mut LoadContext,
+    ) -> BoxedFuture<'a, Result<Self::Asset, Self::Error>> {
+        Box::pin(async move {
+            let mut bytes = Vec::new();
+            reader.read_to_end(&mut bytes).await?;
+            let file: ThemeFile = ron::de::from_bytes(&bytes)?;
+
+            Ok(ThemeStyle {
+                frame: load_context.load(file.frame),
+                clear_color: file.clear_color,
+                ambient_light: AmbientLight {
+                    color: file.ambient_color,
+                    brightness: file.ambient_brightness,
+                },
+                lights: file.lights,
+                block_material: file.block_material,
+                outline_colors: file.outline_colors,
+                push_animation: file.push_animation.sanitized(),
+            })
+        })
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["theme.ron"]
+    }
+}
+
+/// The theme that's currently applied. Until the theme files have loaded, this
+/// matches the default theme as closely as it can without its frame.
+#[derive(Resource, Deref, Clone, Debug)]
+pub struct CurrentTheme(ThemeStyle);
+
+impl Default for CurrentTheme {
+    fn default() -> Self {
+        Self(ThemeStyle {
+            frame: Handle::default(),
+            clear_color: Color::rgb(0.91, 0.76, 0.45),
+            ambient_light: AmbientLight {
+                color: Color::BEIGE,
+                brightness: 0.1,
+            },
+            lights: vec![ThemeLight {
+                position: [3.0, 4.0, 7.0],
+                color: Color::WHITE,
+                intensity: 1500.0,
+            }],
+            block_material: BlockMaterial::default(),
+            outline_colors: ColorPalette::Default.state_colors(),
+            push_animation: PushAnimation::default(),
+        })
+    }
+}
+
+impl CurrentTheme {
+    /// The outline colors to use with the given palette.
+    pub fn state_colors(&self, palette: ColorPalette) -> StateColors {
+        match palette {
+            ColorPalette::Default => self.outline_colors,
+            _ => palette.state_colors(),
+        }
+    }
+}
+
+fn update_theme(
+    mut current: ResMut<CurrentTheme>,
+    mut applied: Local<Option<Theme>>,
+    mut events: EventReader<AssetEvent<ThemeStyle>>,
+    settings: Res<Settings>,
+    themes: Res<ThemeAssets>,
+    styles: Res<Assets<ThemeStyle>>,
+) {
+    let handle = themes.get(settings.theme);
+
+    // Settings change all the time, so only touch the theme if it's actually a
+    // different one, or the file was edited
+    let reloaded = events.read().any(|event| event.is_modified(handle));
+    if *applied == Some(settings.theme) && !reloaded {
+        return;
+    }
+
+    if let Some(style) = styles.get(handle) {
+        current.0 = style.clone();
+        *applied = Some(settings.theme);
+    }
+}
+
+fn apply_theme(
+    theme: Res<CurrentTheme>,
+    gltfs: Res<Assets<Gltf>>,
+    mut clear_color: ResMut<ClearColor>,
+    mut ambient_light: ResMut<AmbientLight>,
+    mut push_animation: ResMut<PushAnimation>,
+    mut frames: Query<&mut Handle<Scene>, With<CubeFrame>>,
+) {
+    clear_color.0 = theme.clear_color;
+    *ambient_light = theme.ambient_light.clone();
+    *push_animation = theme.push_animation.clone();
+
+    // New levels pick up the frame when they're spawned, but the current one
+    // needs swapping over
+    if let Some(frame) = gltfs.get(&theme.frame) {
+        // Theme files are hand-edited, so a bad model shouldn't take the game down
+        let Some(frame_scene) = frame.named_scenes.get("Scene") else {
+            log::warn!("theme frame has no scene named \"Scene\", keeping the old frame");
+            return;
+        };
+
+        for mut scene in &mut frames {
+            // Setting the same scene again would still respawn it
+            if *scene != *frame_scene {
+                *scene = frame_scene.clone();
+            }
+        }
+    }
+}
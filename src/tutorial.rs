@@ -0,0 +1,412 @@
+//! A scripted walkthrough of the first couple of levels, explaining how pushing
+//! one block can knock others back out.
+
+use bevy::prelude::*;
+use bevy_mod_picking::picking_core::Pickable;
+
+use crate::cubes::{
+    Block, BlockCount, BlockState, EntityGraph, KnockOutEvent, PropagateMode, PuzzleLayout,
+    Spotlight, ToggleEvent,
+};
+use crate::loading::FontAssets;
+use crate::localization::LocalizedText;
+use crate::menu::{ButtonColors, IGNORE_PICKING};
+use crate::progress::Progress;
+use crate::settings::Settings;
+use crate::GameState;
+
+pub struct TutorialPlugin;
+
+impl Plugin for TutorialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Tutorial>()
+            .add_systems(
+                OnEnter(GameState::Playing),
+                (start_tutorial, setup_tutorial_overlay).chain(),
+            )
+            .add_systems(
+                Update,
+                (
+                    advance_tutorial,
+                    click_skip_tutorial,
+                    update_tutorial_overlay,
+                    update_spotlight,
+                )
+                    .chain()
+                    .run_if(in_state(GameState::Playing)),
+            )
+            .add_systems(
+                OnExit(GameState::Playing),
+                (cleanup_tutorial_overlay, clear_spotlight),
+            );
+    }
+}
+
+/// Which step of the tutorial the player is on, if it's running at all.
+#[derive(Resource, Default)]
+struct Tutorial {
+    step: Option<usize>,
+}
+
+struct TutorialStep {
+    /// The level this step is shown on. Steps for earlier levels get skipped.
+    level: i16,
+    /// Key for the explanation shown during this step.
+    text: &'static str,
+    /// Which block, if any, to draw the player's attention to.
+    spotlight: SpotlightTarget,
+    advance: Advance,
+}
+
+#[derive(Clone, Copy)]
+enum SpotlightTarget {
+    None,
+    /// A block that's out of place, but would get knocked back out by some other block.
+    Knockable,
+    /// A block that would knock out another block that's already in place.
+    Cascade,
+}
+
+/// What the player needs to do to move on to the next step.
+#[derive(Clone, Copy, PartialEq, Eq)]
+enum Advance {
+    /// Push any block into place.
+    BlockInPosition,
+    /// Get a block knocked back out of place.
+    BlockKnockedOut,
+    /// Finish the level.
+    NextLevel,
+}
+
+const STEPS: &[TutorialStep] = &[
+    TutorialStep {
+        level: 1,
+        text: "tutorial.step1",
+        spotlight: SpotlightTarget::Knockable,
+        advance: Advance::BlockInPosition,
+    },
+    TutorialStep {
+        level: 1,
+        text: "tutorial.step2",
+        spotlight: SpotlightTarget::Cascade,
+        advance: Advance::BlockKnockedOut,
+    },
+    TutorialStep {
+        level: 1,
+        text: "tutorial.step3",
+        spotlight: SpotlightTarget::None,
+        advance: Advance::NextLevel,
+    },
+    TutorialStep {
+        level: 2,
+        text: "tutorial.step4",
+        spotlight: SpotlightTarget::None,
+        advance: Advance::BlockInPosition,
+    },
+    TutorialStep {
+        level: 2,
+        text: "tutorial.step5",
+        spotlight: SpotlightTarget::None,
+        advance: Advance::BlockInPosition,
+    },
+    TutorialStep {
+        level: 2,
+        text: "tutorial.step6",
+        spotlight: SpotlightTarget::None,
+        advance: Advance::BlockInPosition,
+    },
+];
+
+impl Tutorial {
+    /// The step to show on the given level, if any.
+    fn current(&self, level: i16) -> Option<&'static TutorialStep> {
+        self.step
+            .and_then(|step| STEPS.get(step))
+            .filter(|step| step.level == level)
+    }
+}
+
+#[derive(Component)]
+struct TutorialOverlay;
+
+#[derive(Component)]
+struct TutorialText;
+
+#[derive(Component)]
+struct SkipButton;
+
+fn start_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    progress: Res<Progress>,
+    block_count: Res<BlockCount>,
+) {
+    if tutorial.step.is_none() && !progress.tutorial_completed && block_count.0 == 1 {
+        tutorial.step = Some(0);
+    }
+}
+
+fn finish_tutorial(tutorial: &mut Tutorial, progress: &mut Progress) {
+    tutorial.step = None;
+    progress.tutorial_completed = true;
+}
+
+fn advance_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    mut progress: ResMut<Progress>,
+    mut toggles: EventReader<ToggleEvent>,
+    mut knock_outs: EventReader<KnockOutEvent>,
+    blocks: Query<&Block>,
+    block_count: Res<BlockCount>,
+) {
+    let Some(mut step) = tutorial.step else {
+        toggles.clear();
+        knock_outs.clear();
+        return;
+    };
+
+    // Cascades passing through out of place blocks send toggles that don't
+    // match the block's real state, and shouldn't count as the player's doing
+    let pushed_in = toggles
+        .read()
+        .filter(|toggle| {
+            blocks
+                .get(toggle.block)
+                .is_ok_and(|block| block.state == toggle.state)
+        })
+        .filter(|toggle| toggle.state == BlockState::InPosition)
+        .map(|_| Advance::BlockInPosition);
+
+    // Only cascades count as knocking a block out, not the player pulling one
+    // back out themselves
+    let knocked_out = knock_outs.read().map(|_| Advance::BlockKnockedOut);
+
+    for happened in pushed_in.chain(knocked_out) {
+        let Some(current) = STEPS
+            .get(step)
+            .filter(|current| current.level == block_count.0)
+        else {
+            break;
+        };
+
+        if current.advance == happened {
+            step += 1;
+        }
+    }
+
+    // Skip over whatever was meant for levels the player has already moved past
+    while STEPS
+        .get(step)
+        .is_some_and(|current| current.level < block_count.0)
+    {
+        step += 1;
+    }
+
+    if step >= STEPS.len() {
+        finish_tutorial(&mut tutorial, &mut progress);
+    } else if tutorial.step != Some(step) {
+        tutorial.step = Some(step);
+    }
+}
+
+fn setup_tutorial_overlay(
+    mut commands: Commands,
+    tutorial: Res<Tutorial>,
+    block_count: Res<BlockCount>,
+    font_assets: Res<FontAssets>,
+    button_colors: Res<ButtonColors>,
+) {
+    let text_style = TextStyle {
+        font_size: 28.0,
+        ..font_assets.button_style()
+    };
+
+    let step = tutorial.current(block_count.0);
+
+    commands
+        .spawn((
+            TutorialOverlay,
+            IGNORE_PICKING,
+            NodeBundle {
+                style: Style {
+                    position_type: PositionType::Absolute,
+                    bottom: Val::Px(20.0),
+                    width: Val::Percent(100.0),
+                    justify_content: JustifyContent::Center,
+                    ..default()
+                },
+                visibility: if step.is_some() {
+                    Visibility::Inherited
+                } else {
+                    Visibility::Hidden
+                },
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            parent
+                .spawn((
+                    IGNORE_PICKING,
+                    NodeBundle {
+                        style: Style {
+                            max_width: Val::Px(700.0),
+                            padding: UiRect::all(Val::Px(12.0)),
+                            flex_direction: FlexDirection::Column,
+                            align_items: AlignItems::Center,
+                            ..default()
+                        },
+                        background_color: Color::rgba(0.0, 0.0, 0.0, 0.6).into(),
+                        ..default()
+                    },
+                ))
+                .with_children(|parent| {
+                    parent.spawn((
+                        TutorialText,
+                        IGNORE_PICKING,
+                        TextBundle::from_section("", text_style.clone())
+                            .with_text_alignment(TextAlignment::Center),
+                        LocalizedText::new(step.map_or("", |step| step.text)),
+                    ));
+
+                    parent
+                        .spawn((
+                            SkipButton,
+                            // Keep the click from reaching the cube behind the button
+                            Pickable {
+                                should_block_lower: true,
+                                should_emit_events: false,
+                            },
+                            ButtonBundle {
+                                style: Style {
+                                    width: Val::Px(200.0),
+                                    height: Val::Px(36.0),
+                                    margin: UiRect::top(Val::Px(8.0)),
+                                    justify_content: JustifyContent::Center,
+                                    align_items: AlignItems::Center,
+                                    ..default()
+                                },
+                                background_color: button_colors.normal.into(),
+                                ..default()
+                            },
+                        ))
+                        .with_children(|parent| {
+                            parent.spawn(LocalizedText::new("tutorial.skip").bundle(TextStyle {
+                                font_size: 24.0,
+                                ..text_style
+                            }));
+                        });
+                });
+        });
+}
+
+fn click_skip_tutorial(
+    mut tutorial: ResMut<Tutorial>,
+    mut progress: ResMut<Progress>,
+    interaction: Query<&Interaction, (Changed<Interaction>, With<SkipButton>)>,
+) {
+    for interaction in &interaction {
+        if let Interaction::Pressed = *interaction {
+            finish_tutorial(&mut tutorial, &mut progress);
+        }
+    }
+}
+
+fn update_tutorial_overlay(
+    tutorial: Res<Tutorial>,
+    block_count: Res<BlockCount>,
+    mut overlay: Query<&mut Visibility, With<TutorialOverlay>>,
+    mut text: Query<&mut LocalizedText, With<TutorialText>>,
+) {
+    if !tutorial.is_changed() && !block_count.is_changed() {
+        return;
+    }
+
+    let step = tutorial.current(block_count.0);
+
+    for mut visibility in &mut overlay {
+        *visibility = if step.is_some() {
+            Visibility::Inherited
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    if let Some(step) = step {
+        for mut text in &mut text {
+            *text = LocalizedText::new(step.text);
+        }
+    }
+}
+
+fn update_spotlight(
+    mut commands: Commands,
+    tutorial: Res<Tutorial>,
+    block_count: Res<BlockCount>,
+    settings: Res<Settings>,
+    layout: Option<Res<PuzzleLayout>>,
+    graph: Query<&EntityGraph>,
+    blocks: Query<(Entity, &Block, Has<Spotlight>)>,
+) {
+    let Ok(graph) = graph.get_single() else {
+        return;
+    };
+
+    let mode = PropagateMode::current(&settings, layout.as_deref());
+    let target = tutorial
+        .current(block_count.0)
+        .and_then(|step| find_spotlight_target(step.spotlight, graph, mode, &blocks));
+
+    for (entity, _, spotlit) in &blocks {
+        let should_spotlight = Some(entity) == target;
+
+        if spotlit && !should_spotlight {
+            commands.entity(entity).remove::<Spotlight>();
+        } else if !spotlit && should_spotlight {
+            commands.entity(entity).insert(Spotlight);
+        }
+    }
+}
+
+fn find_spotlight_target(
+    target: SpotlightTarget,
+    graph: &EntityGraph,
+    mode: PropagateMode,
+    blocks: &Query<(Entity, &Block, Has<Spotlight>)>,
+) -> Option<Entity> {
+    let mut out_of_place = blocks
+        .iter()
+        .filter(|(_, block, _)| block.state == BlockState::OutOfPlace)
+        .map(|(entity, _, _)| entity);
+
+    match target {
+        SpotlightTarget::None => None,
+        SpotlightTarget::Knockable => {
+            // Only blocks that some other block knocks out are any use for showing off
+            // a cascade in the next step.
+            let knockable: Vec<Entity> = blocks
+                .iter()
+                .flat_map(|(entity, _, _)| graph.affected_by(entity, mode))
+                .collect();
+
+            out_of_place.find(|entity| knockable.contains(entity))
+        }
+        SpotlightTarget::Cascade => out_of_place.find(|&entity| {
+            graph.affected_by(entity, mode).iter().any(|&affected| {
+                blocks
+                    .get(affected)
+                    .is_ok_and(|(_, block, _)| block.state == BlockState::InPosition)
+            })
+        }),
+    }
+}
+
+fn cleanup_tutorial_overlay(mut commands: Commands, overlay: Query<Entity, With<TutorialOverlay>>) {
+    for entity in &overlay {
+        commands.entity(entity).despawn_recursive();
+    }
+}
+
+fn clear_spotlight(mut commands: Commands, blocks: Query<Entity, With<Spotlight>>) {
+    for entity in &blocks {
+        commands.entity(entity).remove::<Spotlight>();
+    }
+}